@@ -1,6 +1,9 @@
-use bevy::{prelude::*, window::close_on_esc};
+use std::time::Duration;
+
+use bevy::{asset::ChangeWatcher, prelude::*, transform::TransformSystem, window::close_on_esc};
 use bevy_demo::*;
-use bevy_egui::{egui, EguiContexts, EguiPlugin};
+use bevy_egui::EguiPlugin;
+use bevy_rapier2d::prelude::PhysicsSet;
 
 fn main() {
     App::new()
@@ -14,26 +17,45 @@ fn main() {
                     }),
                     ..default()
                 })
+                .set(AssetPlugin {
+                    watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(200)),
+                    ..default()
+                })
                 .set(ImagePlugin::default_nearest()),
+            ActionPlugin {
+                bindings: Some("demo/demo.bindings.ron"),
+            },
+            TexturePlugin,
             CollisionPlugin,
             BackgroundPlugin,
             OutlinePlugin,
             Light2dPlugin,
+            WeatherPlugin,
             DebugPlugin,
         ))
         .add_plugins(EguiPlugin)
-        .add_systems(Update, ui_example_system)
-        .add_systems(Startup, (startup, setup_cameras, setup_shelter))
+        .add_systems(
+            Startup,
+            (
+                setup_cameras,
+                apply_deferred,
+                (setup_shelter, setup_minimap),
+            )
+                .chain(),
+        )
         .add_systems(
             Update,
             (
                 day_cycle,
                 debug_control_day_cycle,
+                debug_control_weather.before(WeatherSystems),
                 (
                     update_background_color,
-                    // update_ambient_light,
+                    update_procedural_background,
+                    update_ambient_light,
                     debug_toggle_global_light,
-                ),
+                )
+                    .after(WeatherSystems),
             )
                 .chain(),
         )
@@ -41,47 +63,41 @@ fn main() {
             Update,
             (
                 close_on_esc,
-                (select_person, update_move_intend, update_camera)
+                update_person_transform,
+                update_person_animation.after(CollisionSystems),
+                update_room_occupancy.after(CollisionSystems),
+                (update_minimap_view, update_minimap_markers).after(update_camera),
+                (
+                    select_person,
+                    update_outline_states,
+                    update_move_intend,
+                    control_camera,
+                    control_minimap,
+                    update_camera,
+                    update_post_process,
+                )
                     .chain()
                     .before(BackgroundSystems)
                     .after(CollisionSystems),
             ),
         )
+        .add_systems(
+            PostUpdate,
+            (
+                update_pixel_camera.before(TransformSystem::TransformPropagate),
+                snap_to_pixel_grid
+                    .after(PhysicsSet::Writeback)
+                    .before(TransformSystem::TransformPropagate),
+            ),
+        )
+        .add_event::<CameraEvent>()
+        .add_event::<RoomEvent>()
+        .init_resource::<PixelCamera>()
+        .init_resource::<AmbientLightSettings>()
+        .init_resource::<AmbientLightLevel>()
         .insert_resource(GameDateTime {
             time_ratio: 0.1,
             ..default()
         })
         .run();
 }
-
-#[derive(Default, Resource)]
-struct UiState {
-    intensity: f32,
-    color: [f32; 3],
-}
-
-fn startup(mut commands: Commands) {
-    commands.insert_resource(UiState {
-        intensity: 0.5,
-        color: [1.0, 1.0, 1.0],
-    });
-}
-
-fn ui_example_system(
-    mut ui_state: ResMut<UiState>,
-    mut contexts: EguiContexts,
-    mut materials: ResMut<Assets<Light2dFreeformMaterial>>,
-    light_query: Query<&Handle<Light2dFreeformMaterial>>,
-) {
-    egui::Window::new("Hello").show(contexts.ctx_mut(), |ui| {
-        ui.add(egui::Slider::new(&mut ui_state.intensity, 0.0..=1.0).text("age"));
-        egui::color_picker::color_edit_button_rgb(ui, &mut ui_state.color);
-        ui.label("world");
-    });
-
-    for material_handle in light_query.iter() {
-        let material = materials.get_mut(&material_handle).unwrap();
-        material.intensity = ui_state.intensity;
-        material.color = Color::rgb(ui_state.color[0], ui_state.color[1], ui_state.color[2]);
-    }
-}
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    input::InputSystem,
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+/// Maps keys, mouse and gamepad buttons to `Action`s, read through `Res<Input<Action>>`.
+#[derive(Default)]
+pub struct ActionPlugin {
+    /// Bindings file overriding the defaults, reloaded when it changes.
+    pub bindings: Option<&'static str>,
+}
+
+impl Plugin for ActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Input<Action>>()
+            .init_resource::<ActionBindings>()
+            .add_asset::<ActionBindingsFile>()
+            .init_asset_loader::<ActionBindingsLoader>()
+            .add_systems(
+                PreUpdate,
+                (apply_action_bindings, update_actions)
+                    .chain()
+                    .after(InputSystem),
+            );
+
+        if let Some(path) = self.bindings {
+            app.add_systems(
+                Startup,
+                move |mut commands: Commands, asset_server: Res<AssetServer>| {
+                    commands.insert_resource(ActionBindingsHandle(asset_server.load(path)));
+                },
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    /// Pans the camera while held, following the mouse.
+    CameraPan,
+    CameraZoomIn,
+    CameraZoomOut,
+    CameraLeft,
+    CameraRight,
+    CameraUp,
+    CameraDown,
+    /// Focuses the room under the cursor.
+    CameraFocusRoom,
+    CameraFollowSelected,
+    /// Selects the person under the cursor, or moves the selected one.
+    Select,
+    PauseDayCycle,
+    PreviousHour,
+    NextHour,
+    ToggleGlobalLight,
+    CycleWeather,
+    DebugPhysics,
+    DebugComponents,
+    DebugPathFind,
+    DebugGodRays,
+    DebugBloom,
+    DebugVignette,
+    DebugLights,
+    DebugLightOnly,
+    DebugLightEditor,
+    DebugPixelCamera,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ActionBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Button of any connected gamepad.
+    Gamepad(GamepadButtonType),
+}
+
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ActionBindings(pub BTreeMap<Action, Vec<ActionBinding>>);
+
+impl Default for ActionBindings {
+    fn default() -> Self {
+        use Action::*;
+        use ActionBinding::*;
+        Self(BTreeMap::from([
+            (CameraPan, vec![Mouse(MouseButton::Right)]),
+            (
+                CameraZoomIn,
+                vec![
+                    Key(KeyCode::Equals),
+                    Gamepad(GamepadButtonType::RightTrigger),
+                ],
+            ),
+            (
+                CameraZoomOut,
+                vec![Key(KeyCode::Minus), Gamepad(GamepadButtonType::LeftTrigger)],
+            ),
+            (CameraLeft, vec![Key(KeyCode::A), Key(KeyCode::Left)]),
+            (CameraRight, vec![Key(KeyCode::D), Key(KeyCode::Right)]),
+            (CameraUp, vec![Key(KeyCode::W), Key(KeyCode::Up)]),
+            (CameraDown, vec![Key(KeyCode::S), Key(KeyCode::Down)]),
+            (CameraFocusRoom, vec![Key(KeyCode::F)]),
+            (CameraFollowSelected, vec![Key(KeyCode::C)]),
+            (
+                Select,
+                vec![Mouse(MouseButton::Left), Gamepad(GamepadButtonType::South)],
+            ),
+            (
+                PauseDayCycle,
+                vec![Key(KeyCode::Space), Gamepad(GamepadButtonType::Start)],
+            ),
+            (
+                PreviousHour,
+                vec![Key(KeyCode::Q), Gamepad(GamepadButtonType::DPadLeft)],
+            ),
+            (
+                NextHour,
+                vec![Key(KeyCode::E), Gamepad(GamepadButtonType::DPadRight)],
+            ),
+            (ToggleGlobalLight, vec![Key(KeyCode::L)]),
+            (CycleWeather, vec![Key(KeyCode::R)]),
+            (DebugPhysics, vec![Key(KeyCode::Key1)]),
+            (DebugComponents, vec![Key(KeyCode::Key2)]),
+            (DebugPathFind, vec![Key(KeyCode::Key3)]),
+            (DebugGodRays, vec![Key(KeyCode::Key4)]),
+            (DebugBloom, vec![Key(KeyCode::Key5)]),
+            (DebugVignette, vec![Key(KeyCode::Key6)]),
+            (DebugLights, vec![Key(KeyCode::Key7)]),
+            (DebugLightOnly, vec![Key(KeyCode::Key8)]),
+            (DebugLightEditor, vec![Key(KeyCode::Key9)]),
+            (DebugPixelCamera, vec![Key(KeyCode::Key0)]),
+        ]))
+    }
+}
+
+impl ActionBindings {
+    /// Returns the bindings mapped to more than one action.
+    pub fn conflicts(&self) -> Vec<(ActionBinding, Vec<Action>)> {
+        let mut actions_by_binding: Vec<(ActionBinding, Vec<Action>)> = Vec::new();
+        for (action, bindings) in self.0.iter() {
+            for binding in bindings.iter() {
+                match actions_by_binding.iter_mut().find(|(b, _)| b == binding) {
+                    Some((_, actions)) => actions.push(*action),
+                    None => actions_by_binding.push((*binding, vec![*action])),
+                }
+            }
+        }
+        actions_by_binding.retain(|(_, actions)| actions.len() > 1);
+        actions_by_binding
+    }
+
+    fn pressed(
+        bindings: &[ActionBinding],
+        keys: &Input<KeyCode>,
+        mouse_buttons: &Input<MouseButton>,
+        gamepads: &Gamepads,
+        gamepad_buttons: &Input<GamepadButton>,
+    ) -> bool {
+        bindings.iter().any(|binding| match *binding {
+            ActionBinding::Key(key) => keys.pressed(key),
+            ActionBinding::Mouse(button) => mouse_buttons.pressed(button),
+            ActionBinding::Gamepad(button_type) => gamepads
+                .iter()
+                .any(|gamepad| gamepad_buttons.pressed(GamepadButton::new(gamepad, button_type))),
+        })
+    }
+}
+
+// Asset
+/// Bindings file, actions missing from it keep their default bindings.
+#[derive(Debug, Serialize, Deserialize, TypeUuid, TypePath)]
+#[serde(transparent)]
+#[uuid = "8b3c6f0e-2a57-4d8e-a1c4-6e0f9b2d7c35"]
+pub struct ActionBindingsFile(pub BTreeMap<Action, Vec<ActionBinding>>);
+
+#[derive(Resource)]
+struct ActionBindingsHandle(Handle<ActionBindingsFile>);
+
+#[derive(Default)]
+pub struct ActionBindingsLoader;
+
+impl AssetLoader for ActionBindingsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let bindings = ron::de::from_bytes::<ActionBindingsFile>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(bindings));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["bindings.ron"]
+    }
+}
+
+// System
+fn apply_action_bindings(
+    mut bindings_events: EventReader<AssetEvent<ActionBindingsFile>>,
+    files: Res<Assets<ActionBindingsFile>>,
+    handle: Option<Res<ActionBindingsHandle>>,
+    mut bindings: ResMut<ActionBindings>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+    for event in bindings_events.iter() {
+        let (AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed }) =
+            event
+        else {
+            continue;
+        };
+        if *changed != handle.0 {
+            continue;
+        }
+        let Some(file) = files.get(changed) else {
+            continue;
+        };
+
+        let mut new_bindings = ActionBindings::default();
+        new_bindings.0.extend(file.0.clone());
+        for (binding, actions) in new_bindings.conflicts() {
+            warn!("{:?} is bound to several actions: {:?}", binding, actions);
+        }
+        *bindings = new_bindings;
+    }
+}
+
+fn update_actions(
+    bindings: Res<ActionBindings>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut actions: ResMut<Input<Action>>,
+) {
+    actions.clear();
+    for (action, action_bindings) in bindings.0.iter() {
+        if ActionBindings::pressed(
+            action_bindings,
+            &keys,
+            &mouse_buttons,
+            &gamepads,
+            &gamepad_buttons,
+        ) {
+            actions.press(*action);
+        } else {
+            actions.release(*action);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::BackgroundMaterial;
+
+/// Plays a background texture as a sprite sheet, frames go row by row.
+#[derive(Component, Debug, Clone, Copy, Deserialize)]
+pub struct BackgroundAnimation {
+    pub columns: u32,
+    pub rows: u32,
+    pub frames: u32,
+    pub fps: f32,
+}
+
+impl BackgroundAnimation {
+    pub fn grid(&self) -> UVec2 {
+        UVec2::new(self.columns.max(1), self.rows.max(1))
+    }
+
+    /// Returns the offset and size of the frame shown at `elapsed`, in uv.
+    pub fn frame(&self, elapsed: f32) -> Vec4 {
+        let grid = self.grid();
+        let frames = self.frames.clamp(1, grid.x * grid.y);
+        let index = (elapsed * self.fps) as u32 % frames;
+        let size = 1.0 / grid.as_vec2();
+        let offset = UVec2::new(index % grid.x, index / grid.x).as_vec2() * size;
+        Vec4::new(offset.x, offset.y, size.x, size.y)
+    }
+}
+
+pub fn update_background_animation(
+    time: Res<Time>,
+    mut materials: ResMut<Assets<BackgroundMaterial>>,
+    background_query: Query<(&BackgroundAnimation, &Handle<BackgroundMaterial>)>,
+) {
+    for (animation, material_handle) in &background_query {
+        let frame = animation.frame(time.elapsed_seconds_wrapped());
+        if materials
+            .get(material_handle)
+            .is_some_and(|material| material.frame != frame)
+        {
+            materials.get_mut(material_handle).unwrap().frame = frame;
+        }
+    }
+}
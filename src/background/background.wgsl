@@ -8,7 +8,12 @@
 struct BackgroundMaterial {
     range: vec4<f32>,
     palette_rows: vec4<i32>,
-    palette_ratio: vec2<f32>,
+    palette_ratio: vec4<f32>,
+    frame: vec4<f32>,
+    color_top: vec4<f32>,
+    color_bottom: vec4<f32>,
+    procedural: vec4<f32>,
+    opacity: f32,
 };
 
 @group(1) @binding(0)
@@ -20,12 +25,55 @@ var texture_sampler: sampler;
 @group(1) @binding(3)
 var palette: texture_2d<f32>;
 
+#ifdef ANIMATED
+// Maps the layer uv into the current frame of the sprite sheet.
+fn frame_uv(uv: vec2<f32>) -> vec2<f32> {
+    var local = uv;
+#ifdef REPEAT_X
+    local.x = fract(local.x);
+#endif
+#ifdef REPEAT_Y
+    local.y = fract(local.y);
+#endif
+    // Outside of the frame, sample the transparent border.
+    if (any(local < vec2<f32>(0.0)) || any(local > vec2<f32>(1.0))) {
+        return vec2<f32>(-1.0);
+    }
+    return material.frame.xy + local * material.frame.zw;
+}
+#endif
+
+#ifdef STARFIELD
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(12.9898, 78.233))) * 43758.5453);
+}
+
+// `uv` is in pixels for procedural layers, one star at most per pixel.
+fn starfield(uv: vec2<f32>) -> vec4<f32> {
+    let seed = hash(floor(uv));
+    if (seed < 1.0 - material.procedural.x) {
+        return vec4<f32>(0.0);
+    }
+    let twinkle = 0.6 + 0.4 * sin(material.procedural.z * 3.0 + seed * 6283.0);
+    let alpha = saturate(material.procedural.y * twinkle);
+    return vec4<f32>(material.color_top.rgb, alpha);
+}
+#endif
+
 @fragment
 fn fragment(
     mesh: MeshVertexOutput,
 ) -> @location(0) vec4<f32> {
     var uv = mesh.uv * material.range.zw  + material.range.xy;
     var output_color = vec4<f32>(1.0,1.0,1.0,1.0);
+#ifdef STARFIELD
+    output_color = starfield(uv);
+#else ifdef GRADIENT
+    output_color = mix(material.color_top, material.color_bottom, mesh.uv.y);
+#else
+#ifdef ANIMATED
+    uv = frame_uv(uv);
+#endif
     if (material.palette_rows.x < 0) {
         output_color = textureSample(texture, texture_sampler, uv);
     } else {
@@ -35,8 +83,16 @@ fn fragment(
 
         var output_color1 = textureLoad(palette, vec2(material.palette_rows.x, palette_y), 0);
         var output_color2 = textureLoad(palette, vec2(material.palette_rows.y, palette_y), 0);
-        output_color = output_color1 * material.palette_ratio.x + output_color2 * (1.0 - material.palette_ratio.x);
+        output_color = mix(output_color1, output_color2, material.palette_ratio.x);
+        if (material.palette_rows.z >= 0) {
+            var overlay_color1 = textureLoad(palette, vec2(material.palette_rows.z, palette_y), 0);
+            var overlay_color2 = textureLoad(palette, vec2(material.palette_rows.w, palette_y), 0);
+            var overlay_color = mix(overlay_color1, overlay_color2, material.palette_ratio.y);
+            output_color = mix(output_color, overlay_color, material.palette_ratio.z);
+        }
     }
+#endif
+    output_color.a *= material.opacity;
 #ifdef TONEMAP_IN_SHADER
     output_color = bevy_core_pipeline::tonemapping::tone_mapping(output_color, view.color_grading);
 #endif
@@ -3,19 +3,40 @@ use bevy::{
     prelude::*,
     reflect::TypeUuid,
     render::{
+        mesh::MeshVertexBufferLayout,
         render_resource::{
-            AddressMode, AsBindGroup, FilterMode, SamplerDescriptor, ShaderRef, TextureFormat,
+            AddressMode, AsBindGroup, RenderPipelineDescriptor, ShaderRef,
+            SpecializedMeshPipelineError, TextureFormat,
         },
-        texture::{CompressedImageFormats, ImageSampler, ImageType},
+        texture::DEFAULT_IMAGE_HANDLE,
     },
-    sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
-    window::PrimaryWindow,
+    sprite::{Material2d, Material2dKey, Material2dPlugin, MaterialMesh2dBundle},
 };
 use serde::Deserialize;
 
+use crate::{GameDateTime, TextureLoader, TexturePlugin, TextureSettings};
+
+mod animation;
+mod palette;
+mod procedural;
+mod stack;
+
+pub use animation::*;
+pub use palette::*;
+pub use procedural::*;
+pub use stack::*;
+
+/// Camera followed by backgrounds without a `BackgroundTarget`.
 #[derive(Component)]
 pub struct BackgroundCamera;
 
+/// Binds a background to a camera, for views other than the `BackgroundCamera`.
+///
+/// Background stacks pass it on to their layers, together with their `RenderLayers`, so each
+/// camera can show its own stack with its own parallax.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackgroundTarget(pub Entity);
+
 const BACKGROUND_MATERIAL_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 8841543261533782908);
 
@@ -41,117 +62,210 @@ impl Plugin for BackgroundPlugin {
             Shader::from_wgsl
         );
 
+        if !app.is_plugin_added::<TexturePlugin>() {
+            app.add_plugins(TexturePlugin);
+        }
+
         app.add_plugins(Material2dPlugin::<BackgroundMaterial>::default())
+            .init_resource::<BackgroundPalette>()
+            .add_asset::<BackgroundStack>()
+            .init_asset_loader::<BackgroundStackLoader>()
             .add_systems(
                 Update,
-                update_background_transform.in_set(BackgroundSystems),
+                (
+                    spawn_background_stacks,
+                    update_background_transform,
+                    update_background_animation,
+                )
+                    .chain()
+                    .in_set(BackgroundSystems),
             );
     }
 }
 
 // Material
 pub struct BackgroundMaterialImages {
-    pub size: Vec2,
+    pub repeat: BackgroundRepeat,
     pub texture_handle: Handle<Image>,
     pub palette_handle: Option<Handle<Image>>,
 }
 
 impl BackgroundMaterialImages {
     pub fn simple(
-        images: &mut Assets<Image>,
+        asset_server: &AssetServer,
+        texture_loader: &mut TextureLoader,
         repeat: BackgroundRepeat,
         texture_path: &str,
     ) -> BackgroundMaterialImages {
-        let (mode_u, mode_v) = match repeat {
-            BackgroundRepeat::None => (AddressMode::ClampToBorder, AddressMode::ClampToBorder),
-            BackgroundRepeat::X => (AddressMode::Repeat, AddressMode::ClampToBorder),
-            BackgroundRepeat::Y => (AddressMode::ClampToBorder, AddressMode::Repeat),
-            BackgroundRepeat::XY => (AddressMode::Repeat, AddressMode::Repeat),
-        };
-        let texture = load_texture(texture_path, Option::None, mode_u, mode_v);
-        let size = texture.size();
-        let texture_handle = images.add(texture);
+        let texture_handle =
+            texture_loader.load(asset_server, texture_path, repeat.texture_settings(None));
 
         BackgroundMaterialImages {
-            size,
+            repeat,
             texture_handle,
             palette_handle: None,
         }
     }
 
     pub fn palette(
-        images: &mut Assets<Image>,
+        asset_server: &AssetServer,
+        texture_loader: &mut TextureLoader,
         repeat: BackgroundRepeat,
         texture_path: &str,
         palette_path: &str,
     ) -> BackgroundMaterialImages {
-        let (mode_u, mode_v) = match repeat {
-            BackgroundRepeat::None => (AddressMode::ClampToBorder, AddressMode::ClampToBorder),
-            BackgroundRepeat::X => (AddressMode::Repeat, AddressMode::ClampToBorder),
-            BackgroundRepeat::Y => (AddressMode::ClampToBorder, AddressMode::Repeat),
-            BackgroundRepeat::XY => (AddressMode::Repeat, AddressMode::Repeat),
-        };
-        let texture = load_texture(texture_path, Some(TextureFormat::R16Unorm), mode_u, mode_v);
-        let size = texture.size();
-        let texture_handle = images.add(texture);
+        let texture_handle = texture_loader.load(
+            asset_server,
+            texture_path,
+            repeat.texture_settings(Some(TextureFormat::R16Unorm)),
+        );
 
-        let palette = load_texture(
+        let palette_handle = texture_loader.load(
+            asset_server,
             palette_path,
-            Option::None,
-            AddressMode::ClampToEdge,
-            AddressMode::ClampToEdge,
+            TextureSettings {
+                format: None,
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+            },
         );
-        let palette_handle = images.add(palette);
 
         BackgroundMaterialImages {
-            size,
+            repeat,
             texture_handle,
             palette_handle: Some(palette_handle),
         }
     }
+
+    /// Placeholder images for procedural layers, which don't sample a texture.
+    pub fn procedural() -> BackgroundMaterialImages {
+        BackgroundMaterialImages {
+            repeat: BackgroundRepeat::XY,
+            texture_handle: DEFAULT_IMAGE_HANDLE.typed(),
+            palette_handle: None,
+        }
+    }
 }
 
 #[derive(AsBindGroup, Reflect, Debug, Clone, TypeUuid)]
 #[reflect(Debug)]
+#[bind_group_data(BackgroundMaterialKey)]
 #[uuid = "dc760329-e28c-43c3-89c1-fd145fa35b37"]
 pub struct BackgroundMaterial {
-    pub size: Vec2,
     #[uniform(0)]
     pub range: Vec4,
+    /// Palette columns, `x`/`y` are blended by `palette_ratio.x` and `z`/`w` by
+    /// `palette_ratio.y`. The `z`/`w` blend is faded in by `palette_ratio.z`.
     #[uniform(0)]
     pub palette_rows: IVec4,
     #[uniform(0)]
-    pub palette_ratio: Vec2,
+    pub palette_ratio: Vec4,
+    /// Offset and size of the current sprite sheet frame, in uv.
+    #[uniform(0)]
+    pub frame: Vec4,
+    /// Gradient colors of procedural layers, stars use `color_top`.
+    #[uniform(0)]
+    pub color_top: Color,
+    #[uniform(0)]
+    pub color_bottom: Color,
+    /// Star density, star brightness and time of procedural layers.
+    #[uniform(0)]
+    pub procedural: Vec4,
+    #[uniform(0)]
+    pub opacity: f32,
     #[texture(1)]
     #[sampler(2)]
     pub texture: Handle<Image>,
     #[texture(3)]
     pub palette: Option<Handle<Image>>,
+    pub kind: BackgroundKind,
+    pub repeat: BackgroundRepeat,
 }
 
 impl Material2d for BackgroundMaterial {
     fn fragment_shader() -> ShaderRef {
         BACKGROUND_MATERIAL_SHADER_HANDLE.typed().into()
     }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _: &MeshVertexBufferLayout,
+        key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        if let Some(ref mut fragment) = descriptor.fragment {
+            let key = key.bind_group_data;
+            match key.kind {
+                BackgroundKind::Texture => {}
+                BackgroundKind::Animated => fragment.shader_defs.push("ANIMATED".into()),
+                BackgroundKind::Starfield => fragment.shader_defs.push("STARFIELD".into()),
+                BackgroundKind::Gradient => fragment.shader_defs.push("GRADIENT".into()),
+            }
+            if matches!(key.repeat, BackgroundRepeat::X | BackgroundRepeat::XY) {
+                fragment.shader_defs.push("REPEAT_X".into());
+            }
+            if matches!(key.repeat, BackgroundRepeat::Y | BackgroundRepeat::XY) {
+                fragment.shader_defs.push("REPEAT_Y".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackgroundKind {
+    #[default]
+    Texture,
+    /// Sprite sheet texture, see `BackgroundAnimation`.
+    Animated,
+    Starfield,
+    Gradient,
+}
+
+impl BackgroundKind {
+    pub fn is_procedural(self) -> bool {
+        matches!(self, BackgroundKind::Starfield | BackgroundKind::Gradient)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BackgroundMaterialKey {
+    pub kind: BackgroundKind,
+    pub repeat: BackgroundRepeat,
+}
+
+impl From<&BackgroundMaterial> for BackgroundMaterialKey {
+    fn from(material: &BackgroundMaterial) -> Self {
+        Self {
+            kind: material.kind,
+            repeat: material.repeat,
+        }
+    }
 }
 
 impl BackgroundMaterial {
     pub fn bundle(
         materials: &mut Assets<Self>,
         images: BackgroundMaterialImages,
+        kind: BackgroundKind,
     ) -> MaterialMesh2dBundle<Self> {
         MaterialMesh2dBundle {
             mesh: BACKGROUND_MATERIAL_MESH_HANDLE.typed().into(),
             material: materials.add(BackgroundMaterial {
-                size: images.size,
                 range: Vec4::new(0.0, 0.0, 1.0, 1.0),
                 palette_rows: match images.palette_handle {
                     None => IVec4::NEG_ONE,
-                    Some(_) => IVec4::new(0, 1, 2, 3),
+                    Some(_) => IVec4::new(0, 1, -1, -1),
                 },
-                palette_ratio: Vec2::ZERO,
+                palette_ratio: Vec4::ZERO,
+                frame: Vec4::new(0.0, 0.0, 1.0, 1.0),
+                color_top: Color::WHITE,
+                color_bottom: Color::WHITE,
+                procedural: Vec4::ZERO,
+                opacity: 1.0,
                 texture: images.texture_handle,
                 palette: images.palette_handle,
+                kind,
+                repeat: images.repeat,
             }),
             ..default()
         }
@@ -159,21 +273,46 @@ impl BackgroundMaterial {
 }
 
 // Component
-#[derive(Debug, Deserialize)]
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
 pub enum BackgroundRepeat {
+    #[default]
     None,
     X,
     Y,
     XY,
 }
 
+impl BackgroundRepeat {
+    fn texture_settings(self, format: Option<TextureFormat>) -> TextureSettings {
+        let (address_mode_u, address_mode_v) = match self {
+            BackgroundRepeat::None => (AddressMode::ClampToBorder, AddressMode::ClampToBorder),
+            BackgroundRepeat::X => (AddressMode::Repeat, AddressMode::ClampToBorder),
+            BackgroundRepeat::Y => (AddressMode::ClampToBorder, AddressMode::Repeat),
+            BackgroundRepeat::XY => (AddressMode::Repeat, AddressMode::Repeat),
+        };
+        TextureSettings {
+            format,
+            address_mode_u,
+            address_mode_v,
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Background {
+    /// `0.0` moves with the world, `1.0` stays with the camera. Negative values move faster
+    /// than the world, for foreground layers.
     pub speed: Vec2,
     pub position: Vec2,
     pub scale: f32,
     pub z: f32,
     pub offset: Vec2,
+    /// Drift independent of the camera, in texture pixels per second.
+    pub scroll: Vec2,
+    /// Multiplies the alpha of the layer.
+    pub opacity: f32,
+    /// Accumulated `scroll`, relative to the texture size.
+    scrolled: Vec2,
 }
 
 impl Default for Background {
@@ -184,6 +323,9 @@ impl Default for Background {
             scale: 1.0,
             z: Default::default(),
             offset: Default::default(),
+            scroll: Default::default(),
+            opacity: 1.0,
+            scrolled: Vec2::ZERO,
         }
     }
 }
@@ -194,37 +336,92 @@ pub struct BackgroundBundle {
     pub background: Background,
 }
 
+type BackgroundLayerQuery<'a> = (
+    &'a mut Background,
+    &'a Handle<BackgroundMaterial>,
+    &'a mut Transform,
+    Option<&'a BackgroundAnimation>,
+    Option<&'a BackgroundTarget>,
+);
+
 pub fn update_background_transform(
+    time: Res<Time>,
     mut materials: ResMut<Assets<BackgroundMaterial>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    camera_query: Query<(&Camera, &mut Transform), With<BackgroundCamera>>,
-    mut background_query: Query<
-        (&Background, &Handle<BackgroundMaterial>, &mut Transform),
-        Without<BackgroundCamera>,
-    >,
+    images: Res<Assets<Image>>,
+    default_camera_query: Query<Entity, With<BackgroundCamera>>,
+    camera_query: Query<(&Camera, &Transform, Option<&Parent>)>,
+    parent_query: Query<(&Transform, Option<&Parent>), Without<Background>>,
+    mut background_query: Query<BackgroundLayerQuery, Without<Camera>>,
 ) {
-    let primary_window = window_query.single();
-    let mut window_size = Vec2::new(primary_window.width(), primary_window.height());
+    let default_camera = default_camera_query.get_single().ok();
 
-    let (camera, camera_transform) = camera_query.single();
-    if let Some(viewport) = &camera.viewport {
-        window_size = viewport.physical_size.as_vec2();
-    }
+    for (mut background, material_handle, mut transform, animation, target) in &mut background_query
+    {
+        let Some((camera, camera_transform, parent)) = target
+            .map(|target| target.0)
+            .or(default_camera)
+            .and_then(|camera| camera_query.get(camera).ok())
+        else {
+            continue;
+        };
+        // Screen cameras are children of the main camera, whose propagated `GlobalTransform` is a
+        // frame late.
+        let mut camera_transform = *camera_transform;
+        let mut ancestor = parent.map(|parent| parent.get());
+        while let Some((ancestor_transform, ancestor_parent)) =
+            ancestor.and_then(|entity| parent_query.get(entity).ok())
+        {
+            camera_transform = ancestor_transform.mul_transform(camera_transform);
+            ancestor = ancestor_parent.map(|parent| parent.get());
+        }
+        // Size of the view in world units, including the zoom of the camera.
+        let Some(view_size) = camera
+            .logical_viewport_size()
+            .map(|size| size * camera_transform.scale.truncate())
+        else {
+            continue;
+        };
 
-    for (background, material_handle, mut transform) in &mut background_query {
-        transform.translation = camera_transform.translation.truncate().extend(background.z);
-        transform.scale = window_size.extend(transform.scale.z);
+        let Some(material) = materials.get_mut(material_handle) else {
+            continue;
+        };
+        // Procedural layers are sampled in pixels.
+        let texture_size = if material.kind.is_procedural() {
+            Vec2::ONE
+        } else {
+            // Skip layers whose texture is still loading.
+            let Some(image) = images.get(&material.texture) else {
+                continue;
+            };
+            match animation {
+                Some(animation) => image.size() / animation.grid().as_vec2(),
+                None => image.size(),
+            }
+        };
 
-        let material = materials.get_mut(material_handle).unwrap();
+        transform.translation = camera_transform.translation.truncate().extend(background.z);
+        transform.scale = view_size.extend(transform.scale.z);
 
         let parallax_offset = Vec2::new(1.0, -1.0)
             * (1.0 - background.speed)
             * (camera_transform.translation.truncate() - background.position);
 
-        let relative_size = window_size / material.size / background.scale;
+        // Accumulated rather than computed from the elapsed time, which wraps around.
+        let mut scrolled =
+            background.scrolled + background.scroll * time.delta_seconds() / texture_size;
+        if !material.kind.is_procedural() {
+            scrolled = scrolled.fract();
+        }
+        if background.scrolled != scrolled {
+            background.scrolled = scrolled;
+        }
+        let scroll_offset = Vec2::new(-1.0, 1.0) * scrolled;
+
+        let relative_size = view_size / texture_size / background.scale;
         let relative_position =
-            0.5 + (parallax_offset / material.size) / background.scale + background.offset
-                - relative_size / 2.0;
+            0.5 + (parallax_offset / texture_size) / background.scale + background.offset
+                - relative_size / 2.0
+                + scroll_offset;
 
         material.range = Vec4::new(
             relative_position.x,
@@ -232,42 +429,50 @@ pub fn update_background_transform(
             relative_size.x,
             relative_size.y,
         );
+        material.opacity = background.opacity;
     }
 }
 
-// Util
-fn load_texture(
-    texture_path: &str,
-    texture_format: Option<TextureFormat>,
-    address_mode_u: AddressMode,
-    address_mode_v: AddressMode,
-) -> Image {
-    let real_path = "assets/".to_owned() + texture_path;
-    let ext = std::path::Path::new(&real_path)
-        .extension()
-        .unwrap()
-        .to_str()
-        .unwrap();
-    let img_bytes = std::fs::read(&real_path).unwrap();
-    let mut image = Image::from_buffer(
-        &img_bytes,
-        ImageType::Extension(ext),
-        CompressedImageFormats::all(),
-        true,
-    )
-    .unwrap();
-
-    if let Some(image_format) = texture_format {
-        image.texture_descriptor.format = image_format;
-    }
+pub fn update_procedural_background(
+    time: Res<Time>,
+    game_date_time: Res<GameDateTime>,
+    mut materials: ResMut<Assets<BackgroundMaterial>>,
+    background_query: Query<(&BackgroundProcedural, &Handle<BackgroundMaterial>)>,
+) {
+    let daylight = game_date_time.cos();
+    let lerp_color = |night: Color, day: Color| {
+        let color = Vec4::from(night.as_linear_rgba_f32())
+            .lerp(Vec4::from(day.as_linear_rgba_f32()), daylight);
+        Color::rgba_linear(color.x, color.y, color.z, color.w)
+    };
 
-    image.sampler_descriptor = ImageSampler::Descriptor(SamplerDescriptor {
-        address_mode_u: address_mode_u,
-        address_mode_v: address_mode_v,
-        mag_filter: FilterMode::Nearest,
-        min_filter: FilterMode::Nearest,
-        mipmap_filter: FilterMode::Nearest,
-        ..default()
-    });
-    image
+    for (procedural, material_handle) in &background_query {
+        let Some(material) = materials.get_mut(material_handle) else {
+            continue;
+        };
+        match *procedural {
+            BackgroundProcedural::Starfield {
+                density,
+                brightness,
+                color,
+            } => {
+                material.color_top = color;
+                material.procedural = Vec4::new(
+                    density,
+                    brightness * (1.0 - daylight),
+                    time.elapsed_seconds_wrapped(),
+                    0.0,
+                );
+            }
+            BackgroundProcedural::Gradient {
+                day_top,
+                day_bottom,
+                night_top,
+                night_bottom,
+            } => {
+                material.color_top = lerp_color(night_top, day_top);
+                material.color_bottom = lerp_color(night_bottom, day_bottom);
+            }
+        }
+    }
 }
@@ -0,0 +1,116 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum PaletteCurve {
+    /// Holds the palette until the next keyframe.
+    Step,
+    Linear,
+    /// Cosine ease in and out.
+    #[default]
+    Smooth,
+}
+
+impl PaletteCurve {
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            PaletteCurve::Step => 0.0,
+            PaletteCurve::Linear => t,
+            PaletteCurve::Smooth => 0.5 - (t * PI).cos() / 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaletteKeyframe {
+    /// Time of day in `[0, 1)` at which `row` is fully shown.
+    pub time: f32,
+    /// Column of the palette texture.
+    pub row: i32,
+    /// Interpolation toward the next keyframe.
+    pub curve: PaletteCurve,
+}
+
+impl PaletteKeyframe {
+    pub fn new(time: f32, row: i32, curve: PaletteCurve) -> Self {
+        Self { time, row, curve }
+    }
+}
+
+/// Palette keyframes looping over a day.
+#[derive(Debug, Default, Clone)]
+pub struct PaletteTimeline {
+    keyframes: Vec<PaletteKeyframe>,
+}
+
+impl PaletteTimeline {
+    pub fn new(mut keyframes: Vec<PaletteKeyframe>) -> Self {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self { keyframes }
+    }
+
+    pub fn keyframes(&self) -> &[PaletteKeyframe] {
+        &self.keyframes
+    }
+
+    /// Returns the two palette rows around `time` and the ratio of the second one.
+    pub fn sample(&self, time: f32) -> Option<(i32, i32, f32)> {
+        let time = time.rem_euclid(1.0);
+        let current = self
+            .keyframes
+            .iter()
+            .rposition(|keyframe| keyframe.time <= time)
+            .unwrap_or(self.keyframes.len().checked_sub(1)?);
+        let from = self.keyframes[current];
+        let to = self.keyframes[(current + 1) % self.keyframes.len()];
+
+        let span = (to.time - from.time).rem_euclid(1.0);
+        if span == 0.0 {
+            return Some((from.row, from.row, 0.0));
+        }
+        let elapsed = (time - from.time).rem_euclid(1.0);
+        Some((from.row, to.row, from.curve.ease(elapsed / span)))
+    }
+}
+
+/// Palette animation shared by every palette background.
+#[derive(Resource)]
+pub struct BackgroundPalette {
+    pub time_of_day: PaletteTimeline,
+    /// Palettes cross-faded over the time of day, e.g. for weather.
+    pub overlay: PaletteTimeline,
+    pub overlay_weight: f32,
+}
+
+impl Default for BackgroundPalette {
+    fn default() -> Self {
+        // Rows of `demo/lut.png`: day, night.
+        Self {
+            time_of_day: PaletteTimeline::new(vec![
+                PaletteKeyframe::new(0.0, 1, PaletteCurve::Smooth),
+                PaletteKeyframe::new(0.5, 0, PaletteCurve::Smooth),
+            ]),
+            overlay: default(),
+            overlay_weight: 0.0,
+        }
+    }
+}
+
+impl BackgroundPalette {
+    /// Returns `palette_rows` and `palette_ratio` of a `BackgroundMaterial` at `time`.
+    pub fn sample(&self, time: f32) -> (IVec4, Vec4) {
+        let (row_x, row_y, ratio_x) = self.time_of_day.sample(time).unwrap_or((0, 0, 0.0));
+        match self.overlay.sample(time) {
+            Some((row_z, row_w, ratio_y)) if self.overlay_weight > 0.0 => (
+                IVec4::new(row_x, row_y, row_z, row_w),
+                Vec4::new(ratio_x, ratio_y, self.overlay_weight.min(1.0), 0.0),
+            ),
+            _ => (
+                IVec4::new(row_x, row_y, -1, -1),
+                Vec4::new(ratio_x, 0.0, 0.0, 0.0),
+            ),
+        }
+    }
+}
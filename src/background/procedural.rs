@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::BackgroundKind;
+
+/// Layer generated in the shader instead of sampled from a texture.
+#[derive(Component, Debug, Clone, Deserialize)]
+pub enum BackgroundProcedural {
+    /// Twinkling stars fading in at night.
+    Starfield {
+        /// Fraction of pixels holding a star.
+        density: f32,
+        brightness: f32,
+        color: Color,
+    },
+    /// Vertical sky gradient following the day cycle.
+    Gradient {
+        day_top: Color,
+        day_bottom: Color,
+        night_top: Color,
+        night_bottom: Color,
+    },
+}
+
+impl BackgroundProcedural {
+    pub fn kind(&self) -> BackgroundKind {
+        match self {
+            BackgroundProcedural::Starfield { .. } => BackgroundKind::Starfield,
+            BackgroundProcedural::Gradient { .. } => BackgroundKind::Gradient,
+        }
+    }
+}
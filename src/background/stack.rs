@@ -0,0 +1,178 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    render::view::RenderLayers,
+    utils::{BoxedFuture, HashSet},
+};
+use serde::Deserialize;
+
+use crate::{
+    Background, BackgroundAnimation, BackgroundBundle, BackgroundKind, BackgroundMaterial,
+    BackgroundMaterialImages, BackgroundProcedural, BackgroundRepeat, BackgroundTarget,
+    TextureLoader,
+};
+
+// Asset
+#[derive(Debug, Deserialize, TypeUuid, TypePath)]
+#[uuid = "5f1d2a43-1b7e-4b8c-9b57-0f3c7d1e6a29"]
+pub struct BackgroundStack {
+    pub layers: Vec<BackgroundLayer>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct BackgroundLayer {
+    /// Ignored by procedural layers.
+    pub texture: String,
+    pub palette: Option<String>,
+    pub repeat: BackgroundRepeat,
+    pub speed: Vec2,
+    pub position: Vec2,
+    pub offset: Vec2,
+    pub scale: f32,
+    pub z: f32,
+    pub scroll: Vec2,
+    pub opacity: f32,
+    pub animation: Option<BackgroundAnimation>,
+    pub procedural: Option<BackgroundProcedural>,
+}
+
+impl Default for BackgroundLayer {
+    fn default() -> Self {
+        let background = Background::default();
+        Self {
+            texture: Default::default(),
+            palette: None,
+            repeat: BackgroundRepeat::None,
+            speed: background.speed,
+            position: background.position,
+            offset: background.offset,
+            scale: background.scale,
+            z: background.z,
+            scroll: background.scroll,
+            opacity: background.opacity,
+            animation: None,
+            procedural: None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BackgroundStackLoader;
+
+impl AssetLoader for BackgroundStackLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let stack = ron::de::from_bytes::<BackgroundStack>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(stack));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["background.ron"]
+    }
+}
+
+// System
+type BackgroundStackQuery<'a> = (
+    Entity,
+    &'a Handle<BackgroundStack>,
+    Option<&'a RenderLayers>,
+    Option<&'a BackgroundTarget>,
+);
+
+/// Assets the layers of a stack are spawned from.
+#[derive(SystemParam)]
+pub struct BackgroundLayerAssets<'w> {
+    asset_server: Res<'w, AssetServer>,
+    texture_loader: ResMut<'w, TextureLoader>,
+    materials: ResMut<'w, Assets<BackgroundMaterial>>,
+}
+
+pub fn spawn_background_stacks(
+    mut commands: Commands,
+    mut stack_events: EventReader<AssetEvent<BackgroundStack>>,
+    stacks: Res<Assets<BackgroundStack>>,
+    mut layer_assets: BackgroundLayerAssets,
+    stack_query: Query<BackgroundStackQuery>,
+    changed_query: Query<Entity, Changed<Handle<BackgroundStack>>>,
+) {
+    let mut reloaded = HashSet::new();
+    for event in stack_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = event {
+            reloaded.insert(handle.clone_weak());
+        }
+    }
+
+    for (entity, stack_handle, render_layers, target) in stack_query.iter() {
+        if !reloaded.contains(stack_handle) && !changed_query.contains(entity) {
+            continue;
+        }
+        let Some(stack) = stacks.get(stack_handle) else {
+            continue;
+        };
+
+        commands.entity(entity).despawn_descendants();
+        for layer in stack.layers.iter() {
+            let background_images = match &layer.palette {
+                _ if layer.procedural.is_some() => BackgroundMaterialImages::procedural(),
+                Some(palette) => BackgroundMaterialImages::palette(
+                    &layer_assets.asset_server,
+                    &mut layer_assets.texture_loader,
+                    layer.repeat,
+                    &layer.texture,
+                    palette,
+                ),
+                None => BackgroundMaterialImages::simple(
+                    &layer_assets.asset_server,
+                    &mut layer_assets.texture_loader,
+                    layer.repeat,
+                    &layer.texture,
+                ),
+            };
+            let kind = match (&layer.procedural, layer.animation) {
+                (Some(procedural), _) => procedural.kind(),
+                (None, Some(_)) => BackgroundKind::Animated,
+                (None, None) => BackgroundKind::Texture,
+            };
+            let mut background = commands.spawn(BackgroundBundle {
+                material_bundle: BackgroundMaterial::bundle(
+                    &mut layer_assets.materials,
+                    background_images,
+                    kind,
+                ),
+                background: Background {
+                    speed: layer.speed,
+                    position: layer.position,
+                    scale: layer.scale,
+                    z: layer.z,
+                    offset: layer.offset,
+                    scroll: layer.scroll,
+                    opacity: layer.opacity,
+                    ..default()
+                },
+            });
+            if let Some(animation) = layer.animation {
+                background.insert(animation);
+            }
+            if let Some(procedural) = &layer.procedural {
+                background.insert(procedural.clone());
+            }
+            if let Some(render_layers) = render_layers {
+                background.insert(*render_layers);
+            }
+            if let Some(target) = target {
+                background.insert(*target);
+            }
+            let background = background.id();
+            commands.entity(entity).add_child(background);
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! Deduplicates the pixels of palette variants into a LUT and 16-bit index images.
+//!
+//! Every variant directory holds the same layer images, e.g. `day/1.png` and `night/1.png`.
+//! The LUT has one column per variant and one row per distinct combination of colors, row 0
+//! is a transparent padding for out of bound pixels. Each layer is written as an index image
+//! whose red channel selects the LUT row, see `BackgroundMaterialImages::palette`.
+//!
+//! ```sh
+//! cargo run --bin generate_lut -- assets/demo assets/demo/day assets/demo/night
+//! ```
+//!
+//! With `--check`, nothing is written and the tool fails if the outputs are out of date.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use image::{ImageBuffer, Luma, Rgba, RgbaImage};
+
+/// Largest index an R16Unorm index texture can address.
+const MAX_LUT_INDEX: usize = u16::MAX as usize;
+
+type Color = [u8; 4];
+/// Colors of every variant for one palette entry.
+type LutRow = Vec<Color>;
+
+struct Layer {
+    name: String,
+    width: u32,
+    height: u32,
+    pixels: Vec<LutRow>,
+}
+
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut paths = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.len() < 2 {
+        eprintln!("usage: generate_lut [--check] <output dir> <variant dir>...");
+        return ExitCode::FAILURE;
+    }
+
+    match run(&paths[0], &paths[1..], check) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(output_dir: &Path, variant_dirs: &[PathBuf], check: bool) -> Result<(), String> {
+    let layers = load_layers(variant_dirs)?;
+    let (lut, indices) = generate_lut(&layers, variant_dirs.len())?;
+
+    let mut outputs = vec![(
+        output_dir.join("lut.png"),
+        encode_lut(&lut, variant_dirs.len()),
+    )];
+    for (layer, indice) in layers.iter().zip(indices) {
+        let image: ImageBuffer<Luma<u16>, _> =
+            ImageBuffer::from_raw(layer.width, layer.height, indice).unwrap();
+        outputs.push((output_dir.join(&layer.name), encode_png(image)?));
+    }
+
+    for (path, bytes) in outputs {
+        if check {
+            if fs::read(&path).ok().as_ref() != Some(&bytes) {
+                return Err(format!("{} is out of date", path.display()));
+            }
+        } else {
+            fs::write(&path, bytes).map_err(|err| format!("{}: {err}", path.display()))?;
+        }
+    }
+    println!("{} layers, {} palette entries", layers.len(), lut.len());
+    Ok(())
+}
+
+fn load_layers(variant_dirs: &[PathBuf]) -> Result<Vec<Layer>, String> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(&variant_dirs[0])
+        .map_err(|err| format!("{}: {err}", variant_dirs[0].display()))?
+    {
+        let path = entry.map_err(|err| err.to_string())?.path();
+        if path.extension().is_some_and(|ext| ext == "png") {
+            names.push(path.file_name().unwrap().to_string_lossy().into_owned());
+        }
+    }
+    // Sorted for a deterministic LUT.
+    names.sort();
+
+    let mut layers = Vec::new();
+    for name in names {
+        let mut variants = Vec::new();
+        for dir in variant_dirs {
+            let path = dir.join(&name);
+            let image = image::open(&path)
+                .map_err(|err| format!("{}: {err}", path.display()))?
+                .to_rgba8();
+            variants.push(image);
+        }
+
+        let (width, height) = variants[0].dimensions();
+        if let Some(image) = variants
+            .iter()
+            .find(|image| image.dimensions() != (width, height))
+        {
+            return Err(format!(
+                "{name} is {}x{} in one variant and {width}x{height} in another",
+                image.width(),
+                image.height(),
+            ));
+        }
+
+        let pixels = (0..width * height)
+            .map(|i| {
+                variants
+                    .iter()
+                    .map(|image| image.get_pixel(i % width, i / width).0)
+                    .collect()
+            })
+            .collect();
+        layers.push(Layer {
+            name,
+            width,
+            height,
+            pixels,
+        });
+    }
+    Ok(layers)
+}
+
+/// Returns the LUT rows and the index of every pixel of every layer.
+fn generate_lut(
+    layers: &[Layer],
+    variant_count: usize,
+) -> Result<(Vec<LutRow>, Vec<Vec<u16>>), String> {
+    let mut rows = BTreeMap::new();
+    for layer in layers {
+        for pixel in layer.pixels.iter() {
+            rows.entry(pixel.clone()).or_insert(0);
+        }
+    }
+    if rows.len() > MAX_LUT_INDEX {
+        return Err(format!(
+            "{} distinct colors exceed the limit of {MAX_LUT_INDEX}",
+            rows.len()
+        ));
+    }
+
+    // Padding a transparent row for out of bound pixels.
+    let mut lut = vec![vec![[0; 4]; variant_count]];
+    for (index, (colors, row)) in rows.iter_mut().enumerate() {
+        *row = index as u16 + 1;
+        lut.push(colors.clone());
+    }
+
+    let indices = layers
+        .iter()
+        .map(|layer| layer.pixels.iter().map(|pixel| rows[pixel]).collect())
+        .collect();
+    Ok((lut, indices))
+}
+
+fn encode_lut(lut: &[LutRow], variant_count: usize) -> Vec<u8> {
+    let image = RgbaImage::from_fn(variant_count as u32, lut.len() as u32, |x, y| {
+        Rgba(lut[y as usize][x as usize])
+    });
+    encode_png(image).unwrap()
+}
+
+fn encode_png<P, C>(image: ImageBuffer<P, C>) -> Result<Vec<u8>, String>
+where
+    P: image::PixelWithColorType,
+    [P::Subpixel]: image::EncodableLayout,
+    C: std::ops::Deref<Target = [P::Subpixel]>,
+{
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut bytes, image::ImageOutputFormat::Png)
+        .map_err(|err| err.to_string())?;
+    Ok(bytes.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty directory in the system temp directory, unique to the test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("generate_lut-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_variant(dir: &Path, seed: u8) {
+        fs::create_dir_all(dir).unwrap();
+        for (name, size) in [("1.png", (4, 3)), ("2.png", (2, 5))] {
+            RgbaImage::from_fn(size.0, size.1, |x, y| {
+                Rgba([seed, (x * 40) as u8, (y * 40) as u8, 255])
+            })
+            .save(dir.join(name))
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn output_is_deterministic() {
+        let dir = temp_dir("deterministic");
+        let variant_dirs = [dir.join("day"), dir.join("night")];
+        write_variant(&variant_dirs[0], 10);
+        write_variant(&variant_dirs[1], 200);
+
+        let outputs = ["first", "second"].map(|name| {
+            let output_dir = dir.join(name);
+            fs::create_dir_all(&output_dir).unwrap();
+            run(&output_dir, &variant_dirs, false).unwrap();
+            ["lut.png", "1.png", "2.png"].map(|file| fs::read(output_dir.join(file)).unwrap())
+        });
+        assert_eq!(outputs[0], outputs[1]);
+        run(&dir.join("first"), &variant_dirs, true).unwrap();
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn too_many_palette_entries() {
+        let width = MAX_LUT_INDEX as u32 + 1;
+        let layer = Layer {
+            name: "1.png".into(),
+            width,
+            height: 1,
+            pixels: (0..width)
+                .map(|i| vec![[i as u8, (i >> 8) as u8, (i >> 16) as u8, 255]])
+                .collect(),
+        };
+        assert!(generate_lut(&[layer], 1).is_err());
+    }
+}
@@ -4,13 +4,180 @@ use bevy::{
     window::PrimaryWindow,
 };
 
-use crate::MainCamera;
+use crate::{Action, MainCamera, Room, SelectedPerson, WorldCursor};
 
-#[derive(Default)]
+#[derive(Debug, Default, Clone)]
 pub enum CameraMode {
     #[default]
     Free,
-    Follow(Entity),
+    Follow(CameraFollow),
+    /// Holds the view on `center`, `width` world units wide. Panning or zooming returns to
+    /// `Free`.
+    Focus {
+        center: Vec2,
+        width: f32,
+    },
+    /// Plays the path, ignoring input, then focuses on its last point.
+    Path(CameraPath),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraFollow {
+    pub target: Entity,
+    /// Half size of the region around the view center in which the target moves without
+    /// moving the camera, in world units.
+    pub dead_zone: Vec2,
+    /// Seconds of the target velocity the camera leads by.
+    pub look_ahead: f32,
+}
+
+impl CameraFollow {
+    pub fn new(target: Entity) -> Self {
+        Self {
+            target,
+            dead_zone: Vec2::new(48.0, 24.0),
+            look_ahead: 0.4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraPathPoint {
+    /// Seconds from the start of the path.
+    pub time: f32,
+    pub center: Vec2,
+    /// Width of the view in world units.
+    pub width: f32,
+}
+
+impl CameraPathPoint {
+    pub fn new(time: f32, center: Vec2, width: f32) -> Self {
+        Self {
+            time,
+            center,
+            width,
+        }
+    }
+}
+
+/// Catmull-Rom spline through timed points.
+#[derive(Debug, Default, Clone)]
+pub struct CameraPath {
+    points: Vec<CameraPathPoint>,
+    elapsed: f32,
+}
+
+impl CameraPath {
+    pub fn new(mut points: Vec<CameraPathPoint>) -> Self {
+        points.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self {
+            points,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn points(&self) -> &[CameraPathPoint] {
+        &self.points
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.points.last().map_or(0.0, |point| point.time)
+    }
+
+    /// Returns the view center and width at `time`.
+    pub fn sample(&self, time: f32) -> Option<(Vec2, f32)> {
+        let last = self.points.len().checked_sub(1)?;
+        let next = self
+            .points
+            .iter()
+            .position(|point| point.time > time)
+            .unwrap_or(last + 1);
+        if next == 0 || next > last {
+            let point = self.points[next.min(last)];
+            return Some((point.center, point.width));
+        }
+
+        let p0 = self.points[next.saturating_sub(2)];
+        let p1 = self.points[next - 1];
+        let p2 = self.points[next];
+        let p3 = self.points[(next + 1).min(last)];
+        let t = (time - p1.time) / (p2.time - p1.time);
+        let (t2, t3) = (t * t, t * t * t);
+        let center = 0.5
+            * (2.0 * p1.center
+                + (p2.center - p0.center) * t
+                + (2.0 * p0.center - 5.0 * p1.center + 4.0 * p2.center - p3.center) * t2
+                + (3.0 * p1.center - p0.center - 3.0 * p2.center + p3.center) * t3);
+        Some((center, p1.width + (p2.width - p1.width) * t))
+    }
+}
+
+/// Requests a camera transition, handled by `update_camera`.
+#[derive(Event, Debug, Clone)]
+pub enum CameraEvent {
+    Free,
+    /// Moves the view center there at once, in free mode.
+    Jump(Vec2),
+    Follow(CameraFollow),
+    /// Pans and zooms until the rect fills the view.
+    FocusRect(Rect),
+    /// Focuses on an entity whose scale is its size, like a `Room`.
+    FocusEntity(Entity),
+    Path(CameraPath),
+    /// Adds trauma to the screen shake, `1.0` is the strongest.
+    Shake(f32),
+}
+
+/// Renders the main camera at a low resolution upscaled by an integer zoom, its position snapped
+/// to whole world units, which are texture pixels.
+#[derive(Resource)]
+pub struct PixelCamera {
+    pub enabled: bool,
+    /// Screen pixels per world unit, set by `update_camera`.
+    pub zoom: u32,
+    /// Camera position lost to the snapping, made up for by offsetting the upscaled image.
+    pub remainder: Vec2,
+}
+
+impl Default for PixelCamera {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            zoom: 1,
+            remainder: Vec2::ZERO,
+        }
+    }
+}
+
+impl PixelCamera {
+    /// Offset of the upscaled image making up for the remainder, in screen pixels.
+    pub fn screen_offset(&self) -> Vec2 {
+        (-self.remainder * self.zoom.max(1) as f32).round()
+    }
+
+    /// Converts a window position, e.g. the cursor, to the low resolution viewport of the main
+    /// camera.
+    pub fn window_to_viewport(
+        &self,
+        position: Vec2,
+        window_size: Vec2,
+        viewport_size: Vec2,
+    ) -> Vec2 {
+        if !self.enabled {
+            return position;
+        }
+        let offset = self.screen_offset() * Vec2::new(1.0, -1.0);
+        viewport_size / 2.0 + (position - window_size / 2.0 - offset) / self.zoom.max(1) as f32
+    }
+}
+
+/// Snaps the bottom left corner of the entity to the pixel grid of the `PixelCamera`, through its
+/// `Transform` so that its children follow.
+#[derive(Component, Default)]
+pub struct PixelSnap {
+    /// Translation set by the other systems, restored when the `PixelCamera` is disabled.
+    unsnapped: Option<Vec3>,
+    snapped: Vec3,
 }
 
 #[derive(Resource, Default)]
@@ -20,90 +187,367 @@ pub struct CameraBoundary {
     pub positive: Vec2,
     pub scale_level: i32,
     pub mode: CameraMode,
+    /// Screen shake in `[0, 1]`, decays over time.
+    pub trauma: f32,
+}
+
+impl CameraBoundary {
+    /// Keeps a view of `size` centered at `center` within the boundary. A view larger than the
+    /// boundary is centered on it.
+    pub fn clamp(&self, center: Vec2, size: Vec2) -> Vec2 {
+        let min = self.negative + size / 2.0;
+        let max = self.positive - size / 2.0;
+        let middle = (self.negative + self.positive) / 2.0;
+        Vec2::new(
+            if min.x <= max.x {
+                center.x.clamp(min.x, max.x)
+            } else {
+                middle.x
+            },
+            if min.y <= max.y {
+                center.y.clamp(min.y, max.y)
+            } else {
+                middle.y
+            },
+        )
+    }
 }
 
+// Rates of the exponential smoothing, per second.
+const ZOOM_SMOOTHING: f32 = 10.0;
+const FOLLOW_SMOOTHING: f32 = 6.0;
+const FOCUS_SMOOTHING: f32 = 4.0;
+
+/// Space left around a focused rect.
+const FOCUS_MARGIN: f32 = 1.2;
+/// Largest shake offset, in pixels.
+const SHAKE_OFFSET: f32 = 8.0;
+const SHAKE_DECAY: f32 = 1.5;
+/// Keyboard and screen edge panning, in pixels per second.
+const PAN_SPEED: f32 = 900.0;
+const PAN_ACCELERATION: f32 = 3000.0;
+/// Distance to the window border at which the cursor pans, in pixels.
+const EDGE_PAN_MARGIN: f32 = 8.0;
+
+/// Returns the lerp factor of an exponential smoothing over `delta` seconds.
+fn smoothing_factor(rate: f32, delta: f32) -> f32 {
+    1.0 - (-rate * delta).exp()
+}
+
+#[derive(Default)]
+pub struct CameraState {
+    shake_offset: Vec2,
+    target_position: Option<Vec2>,
+    pan_velocity: Vec2,
+    /// Remainder and scale of the camera before the last pixel snapping.
+    unsnapped: Option<(Vec2, f32)>,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn update_camera(
+    time: Res<Time>,
+    mut state: Local<CameraState>,
     mut boundary: ResMut<CameraBoundary>,
-    mouse_buttons: Res<Input<MouseButton>>,
+    actions: Res<Input<Action>>,
+    mut camera_events: EventReader<CameraEvent>,
     mut wheel_events: EventReader<MouseWheel>,
     mut motion_events: EventReader<MouseMotion>,
     window_query: Query<&Window, With<PrimaryWindow>>,
-    mut camera_query: Query<(&mut Transform, &Camera, &GlobalTransform), With<MainCamera>>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
     target_query: Query<&Transform, Without<MainCamera>>,
+    pixel_camera: Option<ResMut<PixelCamera>>,
 ) {
-    let (mut camera_transform, camera, camera_global_transform) = camera_query.single_mut();
+    let boundary = &mut *boundary;
+    let mut camera_transform = camera_query.single_mut();
     let window = window_query.single();
+    let window_size = Vec2::new(window.width(), window.height());
+    let delta = time.delta_seconds();
 
-    let target_transform = if let CameraMode::Follow(target) = boundary.mode {
-        target_query.get(target).ok()
-    } else {
-        None
-    };
+    // Removes last frame's pixel snapping and shake before moving the camera.
+    if let Some((remainder, scale)) = state.unsnapped.take() {
+        camera_transform.translation += remainder.extend(0.0);
+        camera_transform.scale.x = scale;
+        camera_transform.scale.y = scale;
+    }
+    camera_transform.translation -= state.shake_offset.extend(0.0);
 
-    {
-        let mut scale_level_new = boundary.scale_level;
-        for ev in wheel_events.iter() {
-            if ev.y > 0.0 {
-                scale_level_new += 1;
-            } else {
-                scale_level_new -= 1;
+    for event in camera_events.iter() {
+        let focus_rect = |rect: Rect| CameraMode::Focus {
+            center: rect.center(),
+            width: rect
+                .width()
+                .max(rect.height() * window_size.x / window_size.y)
+                * FOCUS_MARGIN,
+        };
+        match event {
+            CameraEvent::Free => boundary.mode = CameraMode::Free,
+            CameraEvent::Jump(center) => {
+                boundary.mode = CameraMode::Free;
+                camera_transform.translation.x = center.x;
+                camera_transform.translation.y = center.y;
             }
-        }
-        scale_level_new = scale_level_new.clamp(1, 3);
-
-        if scale_level_new != boundary.scale_level {
-            boundary.scale_level = scale_level_new;
-            let scale = boundary.max_width / window.width();
-            let scale = scale / boundary.scale_level as f32;
-            camera_transform.scale.x = scale;
-            camera_transform.scale.y = scale;
-            if let Some(target_transform) = target_transform {
-                camera_transform.translation.x = target_transform.translation.x;
-                camera_transform.translation.y = target_transform.translation.y;
+            CameraEvent::Follow(follow) => boundary.mode = CameraMode::Follow(*follow),
+            CameraEvent::FocusRect(rect) => boundary.mode = focus_rect(*rect),
+            CameraEvent::FocusEntity(entity) => match target_query.get(*entity) {
+                Ok(transform) => {
+                    boundary.mode = focus_rect(Rect::from_center_size(
+                        transform.translation.truncate(),
+                        transform.scale.truncate().abs(),
+                    ))
+                }
+                Err(_) => warn!("Can not focus on {:?}, it has no transform.", entity),
+            },
+            CameraEvent::Path(path) => boundary.mode = CameraMode::Path(path.clone()),
+            CameraEvent::Shake(trauma) => {
+                boundary.trauma = (boundary.trauma + trauma).clamp(0.0, 1.0)
             }
         }
     }
 
+    let mut zoom = 0;
+    for ev in wheel_events.iter() {
+        zoom += if ev.y > 0.0 { 1 } else { -1 };
+    }
+    if actions.just_pressed(Action::CameraZoomIn) {
+        zoom += 1;
+    }
+    if actions.just_pressed(Action::CameraZoomOut) {
+        zoom -= 1;
+    }
+    let mut motion = Vec2::ZERO;
+    if actions.pressed(Action::CameraPan) {
+        for ev in motion_events.iter() {
+            motion += ev.delta;
+        }
+    } else {
+        motion_events.clear();
+    }
+
+    let mut direction = Vec2::ZERO;
+    for (action, axis) in [
+        (Action::CameraLeft, Vec2::NEG_X),
+        (Action::CameraRight, Vec2::X),
+        (Action::CameraUp, Vec2::Y),
+        (Action::CameraDown, Vec2::NEG_Y),
+    ] {
+        if actions.pressed(action) {
+            direction += axis;
+        }
+    }
+
+    // Only explicit input leaves the focus, resting the cursor at an edge doesn't.
+    if matches!(boundary.mode, CameraMode::Focus { .. })
+        && (zoom != 0 || motion != Vec2::ZERO || direction != Vec2::ZERO)
     {
-        let camera_center = match boundary.mode {
-            CameraMode::Free => {
-                let mut motion = Vec2::ZERO;
-                if mouse_buttons.pressed(MouseButton::Right) {
-                    for ev in motion_events.iter() {
-                        motion += ev.delta;
-                    }
-                    motion = camera
-                        .viewport_to_world_2d(camera_global_transform, motion)
-                        .unwrap()
-                        - camera
-                            .viewport_to_world_2d(camera_global_transform, Vec2::new(0.0, 0.0))
-                            .unwrap();
-                }
-                camera_transform.translation.truncate() - motion
+        boundary.mode = CameraMode::Free;
+    }
+    if matches!(boundary.mode, CameraMode::Free) {
+        if let (true, Some(cursor)) = (window.focused, window.cursor_position()) {
+            if cursor.x < EDGE_PAN_MARGIN {
+                direction.x -= 1.0;
+            } else if cursor.x > window_size.x - EDGE_PAN_MARGIN {
+                direction.x += 1.0;
             }
-            CameraMode::Follow(_) => match target_transform {
-                Some(target_transform) => {
-                    const SMOOTHING: f32 = 0.9;
-                    camera_transform.translation.truncate() * SMOOTHING
-                        + target_transform.translation.truncate() * (1.0 - SMOOTHING)
+            // The cursor position goes down from the top of the window.
+            if cursor.y < EDGE_PAN_MARGIN {
+                direction.y += 1.0;
+            } else if cursor.y > window_size.y - EDGE_PAN_MARGIN {
+                direction.y -= 1.0;
+            }
+        }
+        let direction = direction.normalize_or_zero();
+        let velocity_change = direction * PAN_SPEED - state.pan_velocity;
+        state.pan_velocity += velocity_change.clamp_length_max(PAN_ACCELERATION * delta);
+    } else {
+        state.pan_velocity = Vec2::ZERO;
+    }
+
+    let camera_center = camera_transform.translation.truncate();
+    let (target_width, target_center) = match &mut boundary.mode {
+        CameraMode::Free => {
+            boundary.scale_level = (boundary.scale_level + zoom).clamp(1, 3);
+            let pan = motion * Vec2::new(1.0, -1.0) * camera_transform.scale.x;
+            (
+                boundary.max_width / boundary.scale_level as f32,
+                Some(camera_center - pan + state.pan_velocity * delta * camera_transform.scale.x),
+            )
+        }
+        CameraMode::Follow(follow) => {
+            let follow = *follow;
+            boundary.scale_level = (boundary.scale_level + zoom).clamp(1, 3);
+            let width = boundary.max_width / boundary.scale_level as f32;
+            match target_query.get(follow.target) {
+                Ok(target_transform) => {
+                    let target = target_transform.translation.truncate();
+                    let velocity = match state.target_position {
+                        Some(previous) if delta > 0.0 => (target - previous) / delta,
+                        _ => Vec2::ZERO,
+                    };
+                    state.target_position = Some(target);
+
+                    let offset = target + velocity * follow.look_ahead - camera_center;
+                    let outside = offset - offset.clamp(-follow.dead_zone, follow.dead_zone);
+                    (
+                        width,
+                        Some(camera_center.lerp(
+                            camera_center + outside,
+                            smoothing_factor(FOLLOW_SMOOTHING, delta),
+                        )),
+                    )
                 }
-                None => {
-                    println!("Can not get target transform. Set camera to free mode.");
+                Err(_) => {
+                    warn!("Can not get target transform. Set camera to free mode.");
                     boundary.mode = CameraMode::Free;
-                    camera_transform.translation.truncate()
+                    (width, None)
                 }
-            },
+            }
+        }
+        CameraMode::Focus { center, width } => (
+            *width,
+            Some(camera_center.lerp(*center, smoothing_factor(FOCUS_SMOOTHING, delta))),
+        ),
+        CameraMode::Path(path) => {
+            path.elapsed += delta;
+            let (center, width) = path
+                .sample(path.elapsed)
+                .unwrap_or((camera_center, boundary.max_width));
+            if path.elapsed >= path.duration() {
+                boundary.mode = CameraMode::Focus { center, width };
+            }
+            // Paths are followed exactly, both scale and position.
+            camera_transform.scale.x = width / window.width();
+            camera_transform.scale.y = width / window.width();
+            (width, Some(center))
+        }
+    };
+    if !matches!(boundary.mode, CameraMode::Follow(_)) {
+        state.target_position = None;
+    }
+
+    {
+        let target_scale = target_width / window.width();
+        let scale = camera_transform.scale.x;
+        let scale_new = if (target_scale - scale).abs() < 1e-4 {
+            target_scale
+        } else {
+            scale + (target_scale - scale) * smoothing_factor(ZOOM_SMOOTHING, delta)
         };
 
-        let camera_size =
-            Vec2::new(window.width(), window.height()) * camera_transform.scale.truncate();
+        if scale_new != scale {
+            // Keeps the world position under the cursor in place while zooming.
+            if let (CameraMode::Free, Some(cursor)) = (&boundary.mode, window.cursor_position()) {
+                let cursor_offset = (cursor - window_size / 2.0) * Vec2::new(1.0, -1.0);
+                let translation = cursor_offset * (scale - scale_new);
+                camera_transform.translation += translation.extend(0.0);
+            }
+            camera_transform.scale.x = scale_new;
+            camera_transform.scale.y = scale_new;
+        }
+    }
 
-        let negative = camera_center - camera_size / 2.0;
-        let positive = camera_center + camera_size / 2.0;
-        // let camera_center = camera_center
-        //     + (boundary.negative - negative).max(Vec2::ZERO)
-        //     + (boundary.positive - positive).min(Vec2::ZERO);
+    {
+        let camera_center = match target_center {
+            Some(center) => center + camera_transform.translation.truncate() - camera_center,
+            None => camera_transform.translation.truncate(),
+        };
+        let camera_size = window_size * camera_transform.scale.truncate();
+        let camera_center = boundary.clamp(camera_center, camera_size);
         camera_transform.translation.x = camera_center.x;
         camera_transform.translation.y = camera_center.y;
     }
+
+    {
+        boundary.trauma = (boundary.trauma - SHAKE_DECAY * delta).max(0.0);
+        let t = time.elapsed_seconds_wrapped();
+        let noise = Vec2::new((t * 37.0).sin(), (t * 53.0 + 1.3).sin());
+        state.shake_offset =
+            noise * boundary.trauma.powi(2) * SHAKE_OFFSET * camera_transform.scale.x;
+        camera_transform.translation += state.shake_offset.extend(0.0);
+    }
+
+    if let Some(mut pixel_camera) = pixel_camera.filter(|pixel_camera| pixel_camera.enabled) {
+        let scale = camera_transform.scale.x;
+        let center = camera_transform.translation.truncate();
+        let snapped = center.round();
+        pixel_camera.zoom = (1.0 / scale).round().max(1.0) as u32;
+        pixel_camera.remainder = center - snapped;
+        state.unsnapped = Some((pixel_camera.remainder, scale));
+
+        // The low resolution view has one texel per world unit.
+        camera_transform.translation.x = snapped.x;
+        camera_transform.translation.y = snapped.y;
+        camera_transform.scale.x = 1.0;
+        camera_transform.scale.y = 1.0;
+    }
+}
+
+/// Focuses the room under the cursor and follows the selected person, pressing again returns
+/// to free mode.
+pub fn control_camera(
+    actions: Res<Input<Action>>,
+    world_cursor: Res<WorldCursor>,
+    selected_person: Res<SelectedPerson>,
+    boundary: Res<CameraBoundary>,
+    room_query: Query<(Entity, &Transform), With<Room>>,
+    mut camera_events: EventWriter<CameraEvent>,
+) {
+    if actions.just_pressed(Action::CameraFocusRoom) {
+        if matches!(boundary.mode, CameraMode::Focus { .. }) {
+            camera_events.send(CameraEvent::Free);
+        } else if let Some((room, _)) = room_query.iter().find(|(_, transform)| {
+            Rect::from_center_size(transform.translation.truncate(), transform.scale.truncate())
+                .contains(world_cursor.position)
+        }) {
+            camera_events.send(CameraEvent::FocusEntity(room));
+        }
+    }
+    if actions.just_pressed(Action::CameraFollowSelected) {
+        if matches!(boundary.mode, CameraMode::Follow(_)) {
+            camera_events.send(CameraEvent::Free);
+        } else {
+            camera_events.send(CameraEvent::Follow(CameraFollow::new(selected_person.0)));
+        }
+    }
+}
+
+pub fn snap_to_pixel_grid(
+    pixel_camera: Res<PixelCamera>,
+    mut snap_query: Query<(&mut Transform, &mut PixelSnap, Option<&Parent>)>,
+    transform_query: Query<(&Transform, Option<&Parent>), Without<PixelSnap>>,
+) {
+    for (mut transform, mut snap, parent) in snap_query.iter_mut() {
+        // Set by another system since the last snap.
+        if snap.unsnapped.is_none() || transform.translation != snap.snapped {
+            snap.unsnapped = Some(transform.translation);
+        }
+        let unsnapped = snap.unsnapped.unwrap();
+
+        let translation = if pixel_camera.enabled {
+            // The propagated `GlobalTransform` of the parent is a frame late.
+            let mut parent_transform = Transform::IDENTITY;
+            let mut ancestor = parent.map(|parent| parent.get());
+            while let Some((ancestor_transform, ancestor_parent)) =
+                ancestor.and_then(|entity| transform_query.get(entity).ok())
+            {
+                parent_transform = ancestor_transform.mul_transform(parent_transform);
+                ancestor = ancestor_parent.map(|parent| parent.get());
+            }
+            let global = parent_transform.mul_transform(Transform {
+                translation: unsnapped,
+                ..*transform
+            });
+            let half_size = global.scale.truncate() / 2.0;
+            let corner = (global.translation.truncate() - half_size).round();
+            parent_transform
+                .compute_matrix()
+                .inverse()
+                .transform_point3((corner + half_size).extend(global.translation.z))
+        } else {
+            unsnapped
+        };
+        snap.snapped = translation;
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+    }
 }
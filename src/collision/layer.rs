@@ -0,0 +1,112 @@
+use bevy_rapier2d::prelude::*;
+
+/// Named rapier collision groups, build `CollisionGroups` from them with `CollisionLayers`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CollisionLayer {
+    Moveable,
+    /// Moveables climbing a stair, which go through solids.
+    MoveableInStair,
+    Solid,
+    Stair,
+    Selectable,
+    /// The world cursor, picking selectables.
+    Cursor,
+    Trigger,
+    Projectile,
+}
+
+impl CollisionLayer {
+    pub const ALL: [CollisionLayer; 8] = [
+        CollisionLayer::Moveable,
+        CollisionLayer::MoveableInStair,
+        CollisionLayer::Solid,
+        CollisionLayer::Stair,
+        CollisionLayer::Selectable,
+        CollisionLayer::Cursor,
+        CollisionLayer::Trigger,
+        CollisionLayer::Projectile,
+    ];
+
+    pub const fn group(self) -> Group {
+        match self {
+            CollisionLayer::Moveable => Group::GROUP_1,
+            CollisionLayer::MoveableInStair => Group::GROUP_2,
+            CollisionLayer::Solid => Group::GROUP_3,
+            CollisionLayer::Stair => Group::GROUP_4,
+            CollisionLayer::Selectable => Group::GROUP_5,
+            CollisionLayer::Cursor => Group::GROUP_6,
+            CollisionLayer::Trigger => Group::GROUP_7,
+            CollisionLayer::Projectile => Group::GROUP_8,
+        }
+    }
+
+    /// Returns the pairs of layers sharing a group bit, which would collide with each other's
+    /// filters.
+    pub fn overlapping() -> Vec<(CollisionLayer, CollisionLayer)> {
+        let mut overlapping = Vec::new();
+        for (i, layer) in Self::ALL.iter().enumerate() {
+            if layer.group().bits().count_ones() != 1 {
+                overlapping.push((*layer, *layer));
+            }
+            for other in Self::ALL[i + 1..].iter() {
+                if layer.group().intersects(other.group()) {
+                    overlapping.push((*layer, *other));
+                }
+            }
+        }
+        overlapping
+    }
+}
+
+/// Builds `CollisionGroups` from layers, e.g.
+/// `CollisionLayers::member(CollisionLayer::Solid).with(CollisionLayer::Moveable).groups()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionLayers {
+    pub memberships: Group,
+    pub filters: Group,
+}
+
+impl CollisionLayers {
+    pub const fn member(layer: CollisionLayer) -> Self {
+        Self {
+            memberships: layer.group(),
+            filters: Group::NONE,
+        }
+    }
+
+    /// Also belongs to `layer`.
+    pub const fn and(self, layer: CollisionLayer) -> Self {
+        Self {
+            memberships: self.memberships.union(layer.group()),
+            ..self
+        }
+    }
+
+    /// Interacts with the members of `layer`.
+    pub const fn with(self, layer: CollisionLayer) -> Self {
+        Self {
+            filters: self.filters.union(layer.group()),
+            ..self
+        }
+    }
+
+    pub const fn groups(self) -> CollisionGroups {
+        CollisionGroups::new(self.memberships, self.filters)
+    }
+}
+
+impl From<CollisionLayers> for CollisionGroups {
+    fn from(layers: CollisionLayers) -> Self {
+        layers.groups()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_have_distinct_groups() {
+        assert_eq!(CollisionLayer::overlapping(), Vec::new());
+    }
+}
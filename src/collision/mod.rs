@@ -1,11 +1,17 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
+mod layer;
 mod moveable;
+mod picking;
 mod selectable;
+mod trigger;
 
+pub use layer::*;
 pub use moveable::*;
+pub use picking::*;
 pub use selectable::*;
+pub use trigger::*;
 
 #[derive(SystemSet, Clone, PartialEq, Eq, Debug, Hash)]
 pub struct CollisionSystems;
@@ -14,20 +20,25 @@ pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
+        debug_assert!(
+            CollisionLayer::overlapping().is_empty(),
+            "Collision layers share groups: {:?}",
+            CollisionLayer::overlapping()
+        );
+
         app.add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(30.0))
             .init_resource::<WorldCursor>()
+            .init_resource::<PickState>()
+            .add_event::<PickEvent>()
+            .add_event::<TriggerEvent>()
             .add_systems(
                 Update,
-                (update_moveable, update_world_cursor).in_set(CollisionSystems),
+                (
+                    update_moveable,
+                    update_triggers,
+                    (update_world_cursor, update_picking).chain(),
+                )
+                    .in_set(CollisionSystems),
             );
     }
 }
-
-// Groups
-pub const GROUP_MOVEABLE: Group = Group::GROUP_1;
-pub const GROUP_MOVEABLE_IN_STAIR: Group = Group::GROUP_2;
-pub const GROUP_SOLID: Group = Group::GROUP_3;
-pub const GROUP_STAIR: Group = Group::GROUP_4;
-
-pub const GROUP_SELECTABLE: Group = Group::GROUP_5;
-pub const GROUP_SELECTABLE_SENSOR: Group = Group::GROUP_5;
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::{GROUP_MOVEABLE, GROUP_MOVEABLE_IN_STAIR, GROUP_SOLID, GROUP_STAIR};
+use crate::{CollisionLayer, CollisionLayers};
 
 // Component
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
@@ -20,6 +20,33 @@ pub enum MoveIntendVertical {
     Down,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Facing {
+    Left,
+    #[default]
+    Right,
+}
+
+/// Conditions slowing down the walk speed.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SpeedModifiers {
+    pub carrying: bool,
+    pub injured: bool,
+}
+
+impl SpeedModifiers {
+    pub fn factor(&self) -> f32 {
+        let mut factor = 1.0;
+        if self.carrying {
+            factor *= 0.7;
+        }
+        if self.injured {
+            factor *= 0.5;
+        }
+        factor
+    }
+}
+
 #[derive(Debug, Default)]
 pub enum MoveMode {
     #[default]
@@ -28,32 +55,115 @@ pub enum MoveMode {
         start: Vec2,
         direction: Vec2,
     },
+    /// Gravity took over, the fall started at `start_y`.
+    Falling {
+        start_y: f32,
+    },
+    /// Recovers from a fall, standing still for `remaining` seconds.
+    Landing {
+        remaining: f32,
+    },
 }
 
 #[derive(Component, Default)]
 pub struct Moveable {
+    /// Top walk speed, before the modifiers.
     pub speed: f32,
+    /// Horizontal speed gained per second while walking.
+    pub acceleration: f32,
+    /// Horizontal speed lost per second while stopping or turning.
+    pub deceleration: f32,
+    pub modifiers: SpeedModifiers,
+    pub facing: Facing,
     pub intend_horizontal: MoveIntendHorizontal,
     pub intend_vertical: MoveIntendVertical,
     pub mode: MoveMode,
 }
 
+impl Moveable {
+    pub fn max_speed(&self) -> f32 {
+        self.speed * self.modifiers.factor()
+    }
+
+    /// Returns `current` moved toward `target` by the acceleration or deceleration over `delta`
+    /// seconds.
+    fn approach(&self, current: f32, target: f32, delta: f32) -> f32 {
+        let speeding_up = target.abs() > current.abs() && target * current >= 0.0;
+        let rate = if speeding_up {
+            self.acceleration
+        } else {
+            self.deceleration
+        };
+        current + (target - current).clamp(-rate * delta, rate * delta)
+    }
+}
+
+/// How a moveable is simulated, see `moveable_bundle` and `kinematic_moveable_bundle`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MovementMode {
+    #[default]
+    Dynamic,
+    Kinematic,
+}
+
 pub type MoveableBundle = (
     Moveable,
     GravityScale,
     RigidBody,
+    Velocity,
     Collider,
     LockedAxes,
     CollisionGroups,
 );
 pub fn moveable_bundle(speed: f32) -> MoveableBundle {
     (
-        Moveable { speed, ..default() },
+        Moveable {
+            speed,
+            acceleration: speed * 8.0,
+            deceleration: speed * 10.0,
+            ..default()
+        },
         GravityScale(1.0),
         RigidBody::Dynamic,
+        Velocity::zero(),
         Collider::cuboid(0.5, 0.5),
         LockedAxes::ROTATION_LOCKED,
-        CollisionGroups::new(GROUP_MOVEABLE, GROUP_SOLID | GROUP_STAIR),
+        CollisionLayers::member(CollisionLayer::Moveable)
+            .with(CollisionLayer::Solid)
+            .with(CollisionLayer::Stair)
+            .with(CollisionLayer::Trigger)
+            .groups(),
+    )
+}
+
+/// Moves with the rapier `KinematicCharacterController` instead of dynamics, `update_moveable`
+/// applies the gravity.
+pub type KinematicMoveableBundle = (
+    Moveable,
+    GravityScale,
+    RigidBody,
+    Velocity,
+    KinematicCharacterController,
+    Collider,
+    ActiveCollisionTypes,
+    CollisionGroups,
+);
+pub fn kinematic_moveable_bundle(speed: f32) -> KinematicMoveableBundle {
+    let (moveable, gravity, _, velocity, collider, _, groups) = moveable_bundle(speed);
+    (
+        moveable,
+        gravity,
+        RigidBody::KinematicPositionBased,
+        velocity,
+        KinematicCharacterController {
+            filter_flags: QueryFilterFlags::EXCLUDE_SENSORS,
+            filter_groups: Some(groups),
+            ..default()
+        },
+        collider,
+        // Stairs are fixed sensors.
+        ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC,
+        groups,
     )
 }
 
@@ -61,7 +171,9 @@ pub type SolidBundle = (Collider, CollisionGroups);
 pub fn solid_bundle() -> SolidBundle {
     (
         Collider::cuboid(0.5, 0.5),
-        CollisionGroups::new(GROUP_SOLID, GROUP_MOVEABLE),
+        CollisionLayers::member(CollisionLayer::Solid)
+            .with(CollisionLayer::Moveable)
+            .groups(),
     )
 }
 
@@ -74,7 +186,9 @@ pub fn stair_bundle(stair: Vec2) -> StairBundle {
         Stair(stair),
         Sensor,
         Collider::cuboid(0.5, 0.5),
-        CollisionGroups::new(GROUP_STAIR, GROUP_MOVEABLE),
+        CollisionLayers::member(CollisionLayer::Stair)
+            .with(CollisionLayer::Moveable)
+            .groups(),
     )
 }
 
@@ -85,16 +199,31 @@ pub enum MoveIntendStair {
     Far,
 }
 
+// Falling starts above this downward speed and ends below `LANDED_SPEED`.
+const FALL_SPEED: f32 = 60.0;
+const LANDED_SPEED: f32 = 1.0;
+/// Falls higher than this make the moveable stop on landing.
+const MIN_FALL_HEIGHT: f32 = 24.0;
+const LANDING_TIME: f32 = 0.25;
+/// Fraction of the acceleration left while falling.
+const AIR_CONTROL: f32 = 0.3;
+
+type MoveableQuery<'a> = (
+    Entity,
+    &'a mut Moveable,
+    &'a mut GravityScale,
+    &'a mut CollisionGroups,
+    &'a mut Transform,
+    &'a mut Velocity,
+    Option<&'a mut KinematicCharacterController>,
+    Option<&'a KinematicCharacterControllerOutput>,
+);
+
 pub fn update_moveable(
     rapier_context: Res<RapierContext>,
+    rapier_config: Res<RapierConfiguration>,
     time: Res<Time>,
-    mut moveable_query: Query<(
-        Entity,
-        &mut Moveable,
-        &mut GravityScale,
-        &mut CollisionGroups,
-        &mut Transform,
-    )>,
+    mut moveable_query: Query<MoveableQuery>,
     stair_query: Query<(Entity, &Stair, &Transform), Without<Moveable>>,
 ) {
     fn intend_horizontal_to_direction_x(intend_horizontal: MoveIntendHorizontal) -> f32 {
@@ -105,15 +234,28 @@ pub fn update_moveable(
         }
     }
 
+    let delta = time.delta_seconds();
     for (
         moveable_entity,
         mut moveable,
         mut moveable_gravity,
         mut moveable_groups,
         mut moveable_transform,
+        mut moveable_velocity,
+        kinematic_controller,
+        kinematic_output,
     ) in moveable_query.iter_mut()
     {
+        let max_speed = moveable.max_speed();
+        let direction_x = intend_horizontal_to_direction_x(moveable.intend_horizontal);
+        let position = moveable_transform.translation.truncate();
+
         match moveable.mode {
+            MoveMode::Normal if moveable_velocity.linvel.y < -FALL_SPEED => {
+                moveable.mode = MoveMode::Falling {
+                    start_y: position.y,
+                };
+            }
             MoveMode::Normal => {
                 let mut stair_bundle: Option<(Vec2, f32)> = None;
                 for (stair_entity, stair, stair_transform) in stair_query.iter() {
@@ -133,57 +275,56 @@ pub fn update_moveable(
                 }
                 match stair_bundle {
                     Some((stair_direction, stair_x)) => {
-                        let mut direction_x =
-                            intend_horizontal_to_direction_x(moveable.intend_horizontal);
-
-                        let moveable_x = moveable_transform.translation.x;
-                        let direction_to_stair = if moveable_x < stair_x { 1.0 } else { -1.0 };
-                        direction_x += direction_to_stair;
-                        direction_x = direction_x.clamp(-1.0, 1.0);
-
-                        let movement = direction_x * moveable.speed * time.delta_seconds();
-                        moveable_transform.translation.x += movement;
+                        let direction_to_stair = if position.x < stair_x { 1.0 } else { -1.0 };
+                        let direction_x = (direction_x + direction_to_stair).clamp(-1.0, 1.0);
+                        let velocity_x = moveable.approach(
+                            moveable_velocity.linvel.x,
+                            direction_x * max_speed,
+                            delta,
+                        );
+                        moveable_velocity.linvel.x = velocity_x;
 
+                        let next_x = position.x + velocity_x * delta;
                         if false
-                            || moveable_x < stair_x && moveable_transform.translation.x >= stair_x
-                            || moveable_x > stair_x && moveable_transform.translation.x <= stair_x
+                            || position.x < stair_x && next_x >= stair_x
+                            || position.x > stair_x && next_x <= stair_x
                         {
-                            let start = Vec2::new(stair_x, moveable_transform.translation.y);
+                            let start = Vec2::new(stair_x, position.y);
                             moveable_transform.translation.x = start.x;
-                            moveable_transform.translation.y = start.y;
+                            moveable_velocity.linvel = Vec2::ZERO;
                             moveable.mode = MoveMode::InStair {
                                 start,
                                 direction: stair_direction,
                             };
                             moveable_gravity.0 = 0.0;
-                            moveable_groups.memberships = GROUP_MOVEABLE_IN_STAIR;
+                            moveable_groups.memberships = CollisionLayer::MoveableInStair.group();
                         }
                     }
                     None => {
-                        let direction_x =
-                            intend_horizontal_to_direction_x(moveable.intend_horizontal);
-                        let movement = direction_x * moveable.speed * time.delta_seconds();
-                        moveable_transform.translation.x += movement;
+                        moveable_velocity.linvel.x = moveable.approach(
+                            moveable_velocity.linvel.x,
+                            direction_x * max_speed,
+                            delta,
+                        );
                     }
                 }
             }
             MoveMode::InStair { start, direction } => {
-                let follow_1 = intend_horizontal_to_direction_x(moveable.intend_horizontal)
-                    * if direction.x > 0.0 { 1.0 } else { -1.0 };
+                let follow_1 = direction_x * if direction.x > 0.0 { 1.0 } else { -1.0 };
                 let follow_2 = match moveable.intend_vertical {
                     MoveIntendVertical::None => 0.0,
                     MoveIntendVertical::Up => 1.0,
                     MoveIntendVertical::Down => -1.0,
                 } * if direction.y > 0.0 { 1.0 } else { -1.0 };
-                let movement = (follow_1 + follow_2).clamp(-1.0, 1.0)
-                    * direction.normalize()
-                    * moveable.speed
-                    * time.delta_seconds();
-                let position = moveable_transform.translation.truncate();
-                let position = position + movement;
-
-                let position_relative = (position - start) / direction;
-                moveable_transform.translation.x += movement.x;
+                let stair_direction = direction.normalize();
+                let speed = moveable.approach(
+                    moveable_velocity.linvel.dot(stair_direction),
+                    (follow_1 + follow_2).clamp(-1.0, 1.0) * max_speed,
+                    delta,
+                );
+                let velocity = stair_direction * speed;
+
+                let position_relative = (position + velocity * delta - start) / direction;
                 let mut out_stair = false;
                 if position_relative.x < 0.0 || position_relative.y < 0.0 {
                     moveable_transform.translation.y = start.y;
@@ -191,15 +332,275 @@ pub fn update_moveable(
                 } else if position_relative.x > 1.0 || position_relative.y > 1.0 {
                     moveable_transform.translation.y = start.y + direction.y;
                     out_stair = true;
-                } else {
-                    moveable_transform.translation.y += movement.y;
                 }
                 if out_stair {
                     moveable.mode = MoveMode::Normal;
+                    moveable_velocity.linvel = Vec2::new(velocity.x, 0.0);
                     moveable_gravity.0 = 1.0;
-                    moveable_groups.memberships = GROUP_MOVEABLE;
+                    moveable_groups.memberships = CollisionLayer::Moveable.group();
+                } else {
+                    moveable_velocity.linvel = velocity;
                 }
             }
+            MoveMode::Falling { start_y } => {
+                let velocity_x = moveable_velocity.linvel.x;
+                let target_x = direction_x * max_speed;
+                moveable_velocity.linvel.x = velocity_x
+                    + (moveable.approach(velocity_x, target_x, delta) - velocity_x) * AIR_CONTROL;
+
+                if moveable_velocity.linvel.y >= -LANDED_SPEED {
+                    moveable.mode = if start_y - position.y > MIN_FALL_HEIGHT {
+                        MoveMode::Landing {
+                            remaining: LANDING_TIME,
+                        }
+                    } else {
+                        MoveMode::Normal
+                    };
+                }
+            }
+            MoveMode::Landing { remaining } => {
+                moveable_velocity.linvel.x =
+                    moveable.approach(moveable_velocity.linvel.x, 0.0, delta);
+                moveable.mode = if remaining > delta {
+                    MoveMode::Landing {
+                        remaining: remaining - delta,
+                    }
+                } else {
+                    MoveMode::Normal
+                };
+            }
+        }
+
+        let velocity_x = moveable_velocity.linvel.x;
+        if velocity_x.abs() > LANDED_SPEED {
+            let facing = if velocity_x < 0.0 {
+                Facing::Left
+            } else {
+                Facing::Right
+            };
+            if moveable.facing != facing {
+                moveable.facing = facing;
+            }
+        }
+
+        if let Some(mut controller) = kinematic_controller {
+            moveable_velocity.linvel += rapier_config.gravity * moveable_gravity.0 * delta;
+            controller.translation = Some(moveable_velocity.linvel * delta);
+            controller.filter_groups = Some(*moveable_groups);
+            if kinematic_output.is_some_and(|output| output.grounded)
+                && moveable_velocity.linvel.y < 0.0
+            {
+                moveable_velocity.linvel.y = 0.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+    const MOVEMENT_MODES: [MovementMode; 2] = [MovementMode::Dynamic, MovementMode::Kinematic];
+
+    /// Two floors under the default gravity, the moveables stand at `y = 0` on the lower one and
+    /// at `y = 30` on the upper one, which starts at `x = 10`.
+    fn shelter_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            HierarchyPlugin,
+            RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(30.0),
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            DT,
+        )))
+        .insert_resource(RapierConfiguration {
+            timestep_mode: TimestepMode::Fixed {
+                dt: DT,
+                substeps: 1,
+            },
+            ..default()
+        })
+        .add_systems(Update, update_moveable);
+        spawn_box(
+            &mut app,
+            Vec2::new(0.0, -10.0),
+            Vec2::new(200.0, 10.0),
+            solid_bundle(),
+        );
+        spawn_box(
+            &mut app,
+            Vec2::new(60.0, 20.0),
+            Vec2::new(100.0, 10.0),
+            solid_bundle(),
+        );
+        app
+    }
+
+    fn spawn_box<B: Bundle>(app: &mut App, center: Vec2, size: Vec2, bundle: B) -> Entity {
+        app.world
+            .spawn((
+                TransformBundle::from_transform(Transform {
+                    translation: center.extend(0.0),
+                    scale: size.extend(1.0),
+                    ..default()
+                }),
+                bundle,
+            ))
+            .id()
+    }
+
+    fn spawn_moveable(
+        app: &mut App,
+        movement: MovementMode,
+        position: Vec2,
+        intend_vertical: MoveIntendVertical,
+    ) -> Entity {
+        let size = Vec2::new(10.0, 10.0);
+        let moveable = match movement {
+            MovementMode::Dynamic => spawn_box(app, position, size, moveable_bundle(80.0)),
+            MovementMode::Kinematic => {
+                spawn_box(app, position, size, kinematic_moveable_bundle(80.0))
+            }
+        };
+        app.world
+            .get_mut::<Moveable>(moveable)
+            .unwrap()
+            .intend_vertical = intend_vertical;
+        moveable
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Mode {
+        Normal,
+        InStair { start: Vec2, direction: Vec2 },
+        Falling,
+        Landing,
+    }
+
+    /// Runs the app for five seconds, returning the modes the moveable went through and its
+    /// final state.
+    fn run(app: &mut App, moveable: Entity) -> (Vec<Mode>, Vec3, CollisionGroups) {
+        let mut modes = vec![Mode::Normal];
+        for _ in 0..300 {
+            app.update();
+            let mode = match app.world.get::<Moveable>(moveable).unwrap().mode {
+                MoveMode::Normal => Mode::Normal,
+                MoveMode::InStair { start, direction } => Mode::InStair { start, direction },
+                MoveMode::Falling { .. } => Mode::Falling,
+                MoveMode::Landing { .. } => Mode::Landing,
+            };
+            if modes.last() != Some(&mode) {
+                modes.push(mode);
+            }
+        }
+        let entity = app.world.entity(moveable);
+        (
+            modes,
+            entity.get::<Transform>().unwrap().translation,
+            *entity.get::<CollisionGroups>().unwrap(),
+        )
+    }
+
+    /// Checks the moveable took the stair starting at `start_x` once, ending on the floor at
+    /// the other end.
+    fn assert_took_stair(
+        movement: MovementMode,
+        (modes, translation, groups): (Vec<Mode>, Vec3, CollisionGroups),
+        start_x: f32,
+        stair: Vec2,
+    ) {
+        let [Mode::Normal, Mode::InStair { start, direction }, Mode::Normal] = modes[..] else {
+            panic!("{:?}: unexpected mode sequence {:?}", movement, modes);
+        };
+        assert_eq!(start.x, start_x, "{:?}", movement);
+        assert_eq!(direction, stair, "{:?}", movement);
+        assert!(
+            (translation.y - (start.y + direction.y)).abs() < 0.5,
+            "{:?}: ended at {} instead of {}",
+            movement,
+            translation.y,
+            start.y + direction.y
+        );
+        assert_eq!(
+            groups.memberships,
+            CollisionLayer::Moveable.group(),
+            "{:?}",
+            movement
+        );
+    }
+
+    #[test]
+    fn moveable_climbs_stair_up() {
+        for movement in MOVEMENT_MODES {
+            let mut app = shelter_app();
+            let stair = Vec2::new(20.0, 30.0);
+            spawn_box(
+                &mut app,
+                Vec2::ZERO,
+                Vec2::new(20.0, 10.0),
+                stair_bundle(stair),
+            );
+            let moveable = spawn_moveable(
+                &mut app,
+                movement,
+                Vec2::new(-8.0, 0.0),
+                MoveIntendVertical::Up,
+            );
+            assert_took_stair(movement, run(&mut app, moveable), 0.0, stair);
+        }
+    }
+
+    #[test]
+    fn moveable_climbs_stair_down() {
+        for movement in MOVEMENT_MODES {
+            let mut app = shelter_app();
+            let stair = Vec2::new(-20.0, -30.0);
+            spawn_box(
+                &mut app,
+                Vec2::new(20.0, 30.0),
+                Vec2::new(20.0, 10.0),
+                stair_bundle(stair),
+            );
+            let moveable = spawn_moveable(
+                &mut app,
+                movement,
+                Vec2::new(28.0, 30.0),
+                MoveIntendVertical::Down,
+            );
+            assert_took_stair(movement, run(&mut app, moveable), 20.0, stair);
+        }
+    }
+
+    #[test]
+    fn moveable_lands_after_fall() {
+        for movement in MOVEMENT_MODES {
+            let mut app = shelter_app();
+            let moveable = spawn_moveable(
+                &mut app,
+                movement,
+                Vec2::new(-50.0, 60.0),
+                MoveIntendVertical::None,
+            );
+            let (modes, translation, _) = run(&mut app, moveable);
+            assert_eq!(
+                modes,
+                [Mode::Normal, Mode::Falling, Mode::Landing, Mode::Normal],
+                "{:?}",
+                movement
+            );
+            assert!(
+                translation.y.abs() < 0.5,
+                "{:?}: ended at {} instead of 0",
+                movement,
+                translation.y
+            );
         }
     }
 }
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+
+use crate::{Action, WorldCursor};
+
+/// Distance the cursor moves, in window pixels, before a press becomes a drag.
+const DRAG_THRESHOLD: f32 = 4.0;
+
+/// Interactions with the selectable entity on top under the cursor, see `selectable_bundle`.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum PickEvent {
+    HoverEnter(Entity),
+    HoverLeave(Entity),
+    /// `Action::Select` pressed and released on the entity without dragging.
+    Click(Entity),
+    /// `Action::CameraPan` pressed and released on the entity without dragging.
+    ContextMenu(Entity),
+    DragStart(Entity),
+    /// The cursor moved by `delta`, in world units, while dragging the entity.
+    Drag {
+        entity: Entity,
+        delta: Vec2,
+    },
+    DragEnd(Entity),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PickPress {
+    entity: Option<Entity>,
+    screen_position: Vec2,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct PickState {
+    /// The cursor is over an interactive UI node, e.g. the minimap, and picks nothing.
+    pub over_ui: bool,
+    /// Entity on top under the cursor, ignoring the cursor over the UI.
+    pub hovered: Option<Entity>,
+    /// Entity being dragged with `Action::Select`.
+    pub dragging: Option<Entity>,
+    select_press: Option<PickPress>,
+    context_press: Option<PickPress>,
+    last_position: Vec2,
+}
+
+impl PickState {
+    fn moved(press: &PickPress, world_cursor: &WorldCursor) -> bool {
+        press.screen_position.distance(world_cursor.screen_position) > DRAG_THRESHOLD
+    }
+}
+
+pub fn update_picking(
+    world_cursor: Res<WorldCursor>,
+    actions: Res<Input<Action>>,
+    mut state: ResMut<PickState>,
+    mut pick_events: EventWriter<PickEvent>,
+    interaction_query: Query<&Interaction>,
+) {
+    state.over_ui = interaction_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+    let hovered = if state.over_ui {
+        None
+    } else {
+        world_cursor.top
+    };
+    if state.hovered != hovered {
+        if let Some(entity) = state.hovered {
+            pick_events.send(PickEvent::HoverLeave(entity));
+        }
+        if let Some(entity) = hovered {
+            pick_events.send(PickEvent::HoverEnter(entity));
+        }
+        state.hovered = hovered;
+    }
+
+    let press = PickPress {
+        entity: hovered,
+        screen_position: world_cursor.screen_position,
+    };
+    if actions.just_pressed(Action::Select) {
+        state.select_press = Some(press);
+    }
+    if actions.just_pressed(Action::CameraPan) {
+        state.context_press = Some(press);
+    }
+
+    // Drag
+    if let Some(entity) = state.dragging {
+        let delta = world_cursor.position - state.last_position;
+        if delta != Vec2::ZERO {
+            pick_events.send(PickEvent::Drag { entity, delta });
+        }
+    } else if let Some(select_press) = state.select_press {
+        if let Some(entity) = select_press.entity.filter(|_| {
+            actions.pressed(Action::Select) && PickState::moved(&select_press, &world_cursor)
+        }) {
+            pick_events.send(PickEvent::DragStart(entity));
+            state.dragging = Some(entity);
+        }
+    }
+    state.last_position = world_cursor.position;
+
+    // Click
+    if actions.just_released(Action::Select) {
+        if let Some(entity) = state.dragging.take() {
+            pick_events.send(PickEvent::DragEnd(entity));
+        } else if let Some(entity) = state
+            .select_press
+            .filter(|press| press.entity == hovered && !PickState::moved(press, &world_cursor))
+            .and_then(|press| press.entity)
+        {
+            pick_events.send(PickEvent::Click(entity));
+        }
+        state.select_press = None;
+    }
+    if actions.just_released(Action::CameraPan) {
+        if let Some(entity) = state
+            .context_press
+            .filter(|press| press.entity == hovered && !PickState::moved(press, &world_cursor))
+            .and_then(|press| press.entity)
+        {
+            pick_events.send(PickEvent::ContextMenu(entity));
+        }
+        state.context_press = None;
+    }
+}
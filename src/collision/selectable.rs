@@ -3,12 +3,16 @@ use std::collections::HashSet;
 use bevy::{prelude::*, window::PrimaryWindow};
 use bevy_rapier2d::prelude::*;
 
-use crate::{MainCamera, GROUP_SELECTABLE, GROUP_SELECTABLE_SENSOR};
+use crate::{CollisionLayer, CollisionLayers, MainCamera, PixelCamera};
 
 #[derive(Resource, Default)]
 pub struct WorldCursor {
     pub position: Vec2,
+    /// Cursor position in the window, in logical pixels.
+    pub screen_position: Vec2,
     pub entities_below: HashSet<Entity>,
+    /// Entity of `entities_below` drawn on top, the one with the highest z.
+    pub top: Option<Entity>,
 }
 
 pub type SelectableBundle = (Sensor, Collider, CollisionGroups);
@@ -17,7 +21,9 @@ pub fn selectable_bundle() -> SelectableBundle {
     (
         Sensor,
         Collider::cuboid(0.5, 0.5),
-        CollisionGroups::new(GROUP_SELECTABLE, GROUP_SELECTABLE_SENSOR),
+        CollisionLayers::member(CollisionLayer::Selectable)
+            .with(CollisionLayer::Cursor)
+            .groups(),
     )
 }
 
@@ -26,27 +32,55 @@ pub fn update_world_cursor(
     rapier_context: Res<RapierContext>,
     windows_query: Query<&Window, With<PrimaryWindow>>,
     camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    pixel_camera: Option<Res<PixelCamera>>,
+    transform_query: Query<&GlobalTransform>,
 ) {
     let (camera, camera_transform) = camera_query.single();
+    let window = windows_query.single();
 
-    if let Some(cursor_position) = windows_query
-        .single()
-        .cursor_position()
-        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+    let Some(screen_position) = window.cursor_position() else {
+        // Outside of the window, nothing is under the cursor.
+        world_cursor.entities_below.clear();
+        world_cursor.top = None;
+        return;
+    };
+    world_cursor.screen_position = screen_position;
+    let viewport_position = match (&pixel_camera, camera.logical_viewport_size()) {
+        (Some(pixel_camera), Some(viewport_size)) => pixel_camera.window_to_viewport(
+            screen_position,
+            Vec2::new(window.width(), window.height()),
+            viewport_size,
+        ),
+        _ => screen_position,
+    };
+    if let Some(cursor_position) = camera
+        .viewport_to_world(camera_transform, viewport_position)
         .map(|ray| ray.origin.truncate())
     {
         world_cursor.position = cursor_position;
         world_cursor.entities_below.clear();
         rapier_context.intersections_with_point(
             cursor_position,
-            QueryFilter::from(CollisionGroups::new(
-                GROUP_SELECTABLE_SENSOR,
-                GROUP_SELECTABLE,
-            )),
+            QueryFilter::from(
+                CollisionLayers::member(CollisionLayer::Cursor)
+                    .with(CollisionLayer::Selectable)
+                    .groups(),
+            ),
             |entity| {
                 world_cursor.entities_below.insert(entity);
                 true
             },
         );
+        world_cursor.top = world_cursor
+            .entities_below
+            .iter()
+            .filter_map(|entity| {
+                transform_query
+                    .get(*entity)
+                    .ok()
+                    .map(|transform| (*entity, transform.translation().z))
+            })
+            .max_by(|(_, z1), (_, z2)| z1.total_cmp(z2))
+            .map(|(entity, _)| entity);
     }
 }
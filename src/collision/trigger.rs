@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{CollisionLayer, CollisionLayers};
+
+/// Sensor reporting the moveables entering and leaving it through `TriggerEvent`s.
+#[derive(Component, Debug, Default)]
+pub struct TriggerVolume;
+
+pub type TriggerBundle = (
+    TriggerVolume,
+    Sensor,
+    Collider,
+    CollisionGroups,
+    ActiveEvents,
+    ActiveCollisionTypes,
+);
+pub fn trigger_bundle() -> TriggerBundle {
+    (
+        TriggerVolume,
+        Sensor,
+        Collider::cuboid(0.5, 0.5),
+        CollisionLayers::member(CollisionLayer::Trigger)
+            .with(CollisionLayer::Moveable)
+            .with(CollisionLayer::MoveableInStair)
+            .groups(),
+        ActiveEvents::COLLISION_EVENTS,
+        // Kinematic moveables.
+        ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_STATIC,
+    )
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerEvent {
+    Enter { trigger: Entity, entity: Entity },
+    Leave { trigger: Entity, entity: Entity },
+}
+
+pub fn update_triggers(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut trigger_events: EventWriter<TriggerEvent>,
+    trigger_query: Query<(), With<TriggerVolume>>,
+) {
+    for event in collision_events.iter() {
+        let (entity1, entity2, started) = match *event {
+            CollisionEvent::Started(entity1, entity2, _) => (entity1, entity2, true),
+            CollisionEvent::Stopped(entity1, entity2, _) => (entity1, entity2, false),
+        };
+        let (trigger, entity) = if trigger_query.contains(entity1) {
+            (entity1, entity2)
+        } else if trigger_query.contains(entity2) {
+            (entity2, entity1)
+        } else {
+            continue;
+        };
+        trigger_events.send(if started {
+            TriggerEvent::Enter { trigger, entity }
+        } else {
+            TriggerEvent::Leave { trigger, entity }
+        });
+    }
+}
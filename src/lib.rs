@@ -1,13 +1,17 @@
+mod action;
 mod background;
 mod camera;
 mod collision;
 mod light2d;
 mod outline;
 mod shelter;
+mod texture;
 
+pub use action::*;
 pub use background::*;
 pub use camera::*;
 pub use collision::*;
 pub use light2d::*;
 pub use outline::*;
 pub use shelter::*;
+pub use texture::*;
@@ -0,0 +1,92 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{
+            BlendComponent, BlendFactor, BlendOperation, BlendState, ColorTargetState, ColorWrites,
+            RenderPipelineDescriptor, TextureFormat,
+        },
+        texture::BevyDefault,
+        view::ViewTarget,
+    },
+    sprite::Mesh2dPipelineKey,
+};
+
+/// How a light is combined with the lights already drawn into the light buffer.
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Light2dBlendMode {
+    /// Adds the light on top of the buffer.
+    #[default]
+    Additive,
+    /// Multiplies the buffer by the light color, fading to no change at zero attenuation.
+    Multiply,
+    /// Mixes the light color over the buffer, using the attenuation as opacity.
+    Alpha,
+    /// Removes the light from the buffer. Useful for "darkness" lights.
+    Subtract,
+}
+
+impl Light2dBlendMode {
+    fn shader_def(&self) -> &'static str {
+        match self {
+            Light2dBlendMode::Additive => "LIGHT2D_BLEND_ADDITIVE",
+            Light2dBlendMode::Multiply => "LIGHT2D_BLEND_MULTIPLY",
+            Light2dBlendMode::Alpha => "LIGHT2D_BLEND_ALPHA",
+            Light2dBlendMode::Subtract => "LIGHT2D_BLEND_SUBTRACT",
+        }
+    }
+
+    fn blend_component(&self) -> BlendComponent {
+        match self {
+            Light2dBlendMode::Additive => BlendComponent {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            Light2dBlendMode::Multiply => BlendComponent {
+                src_factor: BlendFactor::Dst,
+                dst_factor: BlendFactor::Zero,
+                operation: BlendOperation::Add,
+            },
+            Light2dBlendMode::Alpha => BlendComponent {
+                src_factor: BlendFactor::SrcAlpha,
+                dst_factor: BlendFactor::OneMinusSrcAlpha,
+                operation: BlendOperation::Add,
+            },
+            Light2dBlendMode::Subtract => BlendComponent {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::ReverseSubtract,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Light2dMaterialKey {
+    pub blend_mode: Light2dBlendMode,
+}
+
+pub(super) fn specialize_light2d(
+    descriptor: &mut RenderPipelineDescriptor,
+    mesh_key: Mesh2dPipelineKey,
+    key: Light2dMaterialKey,
+) {
+    if let Some(ref mut fragment) = descriptor.fragment {
+        let format = if mesh_key.contains(Mesh2dPipelineKey::HDR) {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+        fragment.targets = vec![Some(ColorTargetState {
+            format,
+            blend: Some(BlendState {
+                color: key.blend_mode.blend_component(),
+                alpha: BlendComponent::REPLACE,
+            }),
+            write_mask: ColorWrites::ALL,
+        })];
+        fragment
+            .shader_defs
+            .push(key.blend_mode.shader_def().into());
+    }
+}
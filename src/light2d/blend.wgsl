@@ -0,0 +1,13 @@
+#define_import_path bevy_demo::light2d_blend
+
+// Turns a light color and its attenuated strength into the fragment
+// output expected by the blend state of the current blend mode.
+fn blend_light(color: vec4<f32>, strength: f32) -> vec4<f32> {
+#ifdef LIGHT2D_BLEND_MULTIPLY
+    return mix(vec4<f32>(1.0), color, strength);
+#else ifdef LIGHT2D_BLEND_ALPHA
+    return vec4<f32>(color.rgb, saturate(strength));
+#else
+    return strength * color;
+#endif
+}
@@ -0,0 +1,247 @@
+use std::f32::consts::E;
+
+use bevy::{
+    asset::{Asset, HandleId},
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::ImageSampler,
+    },
+};
+
+use super::{
+    Light2dFreeformMaterial, Light2dPointMaterial, Light2dSpriteMaterial,
+    LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE,
+};
+
+const LOOKUP_WIDTH: usize = 2048;
+const LOOKUP_EXPONENTIAL_ROWS: usize = 128;
+const LOOKUP_CURVE_ROWS: usize = 128;
+const LOOKUP_HEIGHT: usize = LOOKUP_EXPONENTIAL_ROWS + LOOKUP_CURVE_ROWS;
+
+const BUILTIN_CURVE_LINEAR: usize = 0;
+const BUILTIN_CURVE_INVERSE_SQUARE: usize = 1;
+const BUILTIN_CURVES: usize = 2;
+
+/// Maximum number of curves that can be registered in [`Light2dFalloffCurves`].
+pub const LIGHT2D_FALLOFF_MAX_CURVES: usize = LOOKUP_CURVE_ROWS - BUILTIN_CURVES;
+
+/// Maps the attenuation of a light, `1.0` at the center and `0.0` at the edge,
+/// to its final strength.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+pub enum Light2dFalloff {
+    /// The default exponential family, `0.0` is the sharpest and `1.0` the softest.
+    Exponential(f32),
+    Linear,
+    /// Inverse square law, windowed to reach zero at the edge of the light.
+    InverseSquare,
+    /// A curve registered in [`Light2dFalloffCurves`].
+    Curve(Light2dFalloffCurveId),
+}
+
+impl Default for Light2dFalloff {
+    fn default() -> Self {
+        Light2dFalloff::Exponential(0.5)
+    }
+}
+
+impl Light2dFalloff {
+    /// Vertical texture coordinate of the curve in the falloff lookup atlas.
+    pub fn lookup_coordinate(&self) -> f32 {
+        let curve_row = |index: usize| (LOOKUP_EXPONENTIAL_ROWS + index) as f32 + 0.5;
+        let row = match self {
+            Light2dFalloff::Exponential(falloff) => {
+                falloff.clamp(0.0, 1.0) * (LOOKUP_EXPONENTIAL_ROWS - 1) as f32 + 0.5
+            }
+            Light2dFalloff::Linear => curve_row(BUILTIN_CURVE_LINEAR),
+            Light2dFalloff::InverseSquare => curve_row(BUILTIN_CURVE_INVERSE_SQUARE),
+            Light2dFalloff::Curve(id) => curve_row(BUILTIN_CURVES + id.0),
+        };
+        row / LOOKUP_HEIGHT as f32
+    }
+}
+
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Light2dFalloffCurveId(usize);
+
+#[derive(Debug, Clone)]
+pub enum Light2dFalloffCurve {
+    /// Piecewise linear curve through `(attenuation, strength)` keyframes.
+    Keyframes(Vec<Vec2>),
+    /// Red channel of the first row of an image, from the edge to the center.
+    Image(Handle<Image>),
+}
+
+impl Light2dFalloffCurve {
+    fn sample(&self, images: &Assets<Image>, x: f32) -> Option<f32> {
+        match self {
+            Light2dFalloffCurve::Keyframes(keyframes) => {
+                let first = keyframes.first()?;
+                let last = keyframes.last()?;
+                if x <= first.x {
+                    return Some(first.y);
+                }
+                if x >= last.x {
+                    return Some(last.y);
+                }
+                keyframes.windows(2).find_map(|pair| {
+                    let (a, b) = (pair[0], pair[1]);
+                    if x < a.x || x > b.x {
+                        None
+                    } else if b.x == a.x {
+                        Some(b.y)
+                    } else {
+                        Some(a.y + (b.y - a.y) * (x - a.x) / (b.x - a.x))
+                    }
+                })
+            }
+            Light2dFalloffCurve::Image(handle) => {
+                let image = images.get(handle)?;
+                let width = image.texture_descriptor.size.width as usize;
+                let position = x.clamp(0.0, 1.0) * (width - 1) as f32;
+                let left = image_red_channel(image, position.floor() as usize)?;
+                let right = image_red_channel(image, position.ceil() as usize)?;
+                Some(left + (right - left) * position.fract())
+            }
+        }
+    }
+}
+
+fn image_red_channel(image: &Image, x: usize) -> Option<f32> {
+    let offset = x * image.texture_descriptor.format.block_size(None)? as usize;
+    match image.texture_descriptor.format {
+        TextureFormat::R8Unorm | TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => {
+            Some(*image.data.get(offset)? as f32 / 255.0)
+        }
+        TextureFormat::R16Unorm | TextureFormat::Rgba16Unorm => {
+            let bytes = image.data.get(offset..offset + 2)?;
+            Some(u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 65535.0)
+        }
+        TextureFormat::R32Float | TextureFormat::Rgba32Float => {
+            let bytes = image.data.get(offset..offset + 4)?;
+            Some(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        }
+        _ => None,
+    }
+}
+
+/// Custom falloff curves, baked into the falloff lookup atlas shared by all lights.
+#[derive(Resource, Default)]
+pub struct Light2dFalloffCurves {
+    curves: Vec<Light2dFalloffCurve>,
+}
+
+impl Light2dFalloffCurves {
+    pub fn add(&mut self, curve: Light2dFalloffCurve) -> Light2dFalloffCurveId {
+        assert!(
+            self.curves.len() < LIGHT2D_FALLOFF_MAX_CURVES,
+            "Too many falloff curves, at most {} are supported.",
+            LIGHT2D_FALLOFF_MAX_CURVES
+        );
+        self.curves.push(curve);
+        Light2dFalloffCurveId(self.curves.len() - 1)
+    }
+
+    pub fn get(&self, id: Light2dFalloffCurveId) -> Option<&Light2dFalloffCurve> {
+        self.curves.get(id.0)
+    }
+
+    pub fn set(&mut self, id: Light2dFalloffCurveId, curve: Light2dFalloffCurve) {
+        self.curves[id.0] = curve;
+    }
+}
+
+pub(super) fn create_falloff_lookup_image(
+    curves: &Light2dFalloffCurves,
+    images: &Assets<Image>,
+) -> Image {
+    let mut data = Vec::with_capacity(LOOKUP_WIDTH * LOOKUP_HEIGHT * 4);
+    let mut push_row = |falloff: &dyn Fn(f32) -> f32| {
+        for x in 0..LOOKUP_WIDTH {
+            let alpha: f32 = x as f32 / LOOKUP_WIDTH as f32;
+            for u in falloff(alpha).to_bits().to_le_bytes() {
+                data.push(u);
+            }
+        }
+    };
+
+    for y in 0..LOOKUP_EXPONENTIAL_ROWS {
+        let intensity: f32 = y as f32 / (LOOKUP_EXPONENTIAL_ROWS - 1) as f32;
+        push_row(&|alpha| alpha.powf(E.powf(1.5 - 3.0 * intensity)));
+    }
+
+    push_row(&|alpha| alpha);
+    push_row(&|alpha| {
+        const K: f32 = 25.0;
+        let distance = 1.0 - alpha;
+        (1.0 / (1.0 + K * distance * distance) - 1.0 / (1.0 + K)) * (1.0 + K) / K
+    });
+    for curve in curves.curves.iter() {
+        push_row(&|alpha| curve.sample(images, alpha).unwrap_or(alpha));
+    }
+    for _ in BUILTIN_CURVES + curves.curves.len()..LOOKUP_CURVE_ROWS {
+        push_row(&|alpha| alpha);
+    }
+
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: LOOKUP_WIDTH as u32,
+            height: LOOKUP_HEIGHT as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &data[..],
+        TextureFormat::R32Float,
+    );
+    image.sampler_descriptor = ImageSampler::Descriptor(ImageSampler::linear_descriptor());
+    image
+}
+
+pub(super) fn update_falloff_lookup_image(
+    curves: Res<Light2dFalloffCurves>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut images: ResMut<Assets<Image>>,
+    mut freeform_materials: ResMut<Assets<Light2dFreeformMaterial>>,
+    mut point_materials: ResMut<Assets<Light2dPointMaterial>>,
+    mut sprite_materials: ResMut<Assets<Light2dSpriteMaterial>>,
+) {
+    let mut image_changed = false;
+    for event in image_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = event {
+            image_changed |= curves.curves.iter().any(|curve| match curve {
+                Light2dFalloffCurve::Image(image) => image == handle,
+                _ => false,
+            });
+        }
+    }
+    if !curves.is_changed() && !image_changed {
+        return;
+    }
+
+    let image = create_falloff_lookup_image(&curves, &images);
+    images.set_untracked(LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE, image);
+
+    let lookup = LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE.typed::<Image>();
+    mark_modified(&mut freeform_materials, |material| {
+        material.falloff_lookup == lookup
+    });
+    mark_modified(&mut point_materials, |material| {
+        material.falloff_lookup == lookup
+    });
+    mark_modified(&mut sprite_materials, |material| {
+        material.falloff_lookup == lookup
+    });
+}
+
+/// Sends a modified event for the filtered materials, so that their bind groups are rebuilt with
+/// the new lookup image. Bind groups are only prepared again when the material itself changes.
+fn mark_modified<M: Asset>(materials: &mut Assets<M>, filter: impl Fn(&M) -> bool) {
+    let ids: Vec<HandleId> = materials
+        .iter()
+        .filter(|(_, material)| filter(material))
+        .map(|(id, _)| id)
+        .collect();
+    for id in ids {
+        materials.get_mut(&Handle::weak(id));
+    }
+}
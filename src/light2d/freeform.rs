@@ -3,29 +3,30 @@ use bevy::{
     reflect::TypeUuid,
     render::{
         mesh::{Indices, MeshVertexBufferLayout},
+        render_asset::RenderAssets,
         render_resource::{
-            AsBindGroup, PrimitiveTopology, RenderPipelineDescriptor, ShaderRef,
-            SpecializedMeshPipelineError,
+            AsBindGroup, AsBindGroupShaderType, PrimitiveTopology, RenderPipelineDescriptor,
+            ShaderRef, ShaderType, SpecializedMeshPipelineError,
         },
     },
     sprite::{Material2d, Material2dKey},
 };
 
 use super::{
-    create_light2d_fragment_target, LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE,
-    LIGHT2D_FREEFORM_MATERIAL_SHADER_HANDLE,
+    specialize_light2d, Light2dBlendMode, Light2dFalloff, Light2dMaterialKey,
+    LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE, LIGHT2D_FREEFORM_MATERIAL_SHADER_HANDLE,
 };
 
 #[derive(AsBindGroup, Reflect, Debug, Clone, TypeUuid)]
 #[reflect(Debug)]
+#[bind_group_data(Light2dMaterialKey)]
+#[uniform(0, Light2dFreeformMaterialUniform)]
 #[uuid = "910394c9-b37b-4996-80fb-43f65f0c84c4"]
 pub struct Light2dFreeformMaterial {
-    #[uniform(0)]
     pub color: Color,
-    #[uniform(0)]
     pub intensity: f32,
-    #[uniform(0)]
-    pub falloff: f32,
+    pub falloff: Light2dFalloff,
+    pub blend_mode: Light2dBlendMode,
     #[texture(1)]
     #[sampler(2)]
     pub falloff_lookup: Handle<Image>,
@@ -36,12 +37,33 @@ impl Default for Light2dFreeformMaterial {
         Self {
             color: Color::WHITE,
             intensity: 1.0,
-            falloff: 0.5,
+            falloff: Light2dFalloff::default(),
+            blend_mode: Light2dBlendMode::Additive,
             falloff_lookup: LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE.clone().typed(),
         }
     }
 }
 
+#[derive(Clone, Default, ShaderType)]
+pub struct Light2dFreeformMaterialUniform {
+    pub color: Vec4,
+    pub intensity: f32,
+    pub falloff: f32,
+}
+
+impl AsBindGroupShaderType<Light2dFreeformMaterialUniform> for Light2dFreeformMaterial {
+    fn as_bind_group_shader_type(
+        &self,
+        _images: &RenderAssets<Image>,
+    ) -> Light2dFreeformMaterialUniform {
+        Light2dFreeformMaterialUniform {
+            color: self.color.as_linear_rgba_f32().into(),
+            intensity: self.intensity,
+            falloff: self.falloff.lookup_coordinate(),
+        }
+    }
+}
+
 impl Material2d for Light2dFreeformMaterial {
     fn fragment_shader() -> ShaderRef {
         LIGHT2D_FREEFORM_MATERIAL_SHADER_HANDLE.typed().into()
@@ -50,16 +72,24 @@ impl Material2d for Light2dFreeformMaterial {
     fn specialize(
         descriptor: &mut RenderPipelineDescriptor,
         _: &MeshVertexBufferLayout,
-        _: Material2dKey<Self>,
+        key: Material2dKey<Self>,
     ) -> Result<(), SpecializedMeshPipelineError> {
+        specialize_light2d(descriptor, key.mesh_key, key.bind_group_data);
         if let Some(ref mut fragment) = descriptor.fragment {
-            fragment.targets = vec![Option::Some(create_light2d_fragment_target())];
             fragment.shader_defs.push("VERTEX_COLORS".into());
         }
         Ok(())
     }
 }
 
+impl From<&Light2dFreeformMaterial> for Light2dMaterialKey {
+    fn from(material: &Light2dFreeformMaterial) -> Self {
+        Self {
+            blend_mode: material.blend_mode,
+        }
+    }
+}
+
 pub fn freeform_polygon_mesh(positions: Vec<Vec2>, extend: f32) -> Mesh {
     let sides = positions.len();
     debug_assert!(sides > 2, "RegularPolygon requires at least 3 sides.");
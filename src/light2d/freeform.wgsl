@@ -1,4 +1,5 @@
 #import bevy_sprite::mesh2d_vertex_output  MeshVertexOutput
+#import bevy_demo::light2d_blend  blend_light
 
 struct Light {
     color: vec4<f32>,
@@ -22,6 +23,5 @@ fn fragment(
         falloff_lookup_sampler,
         vec2<f32>(mesh.color.a, light.falloff)
     ).r;
-    let output_color = attenuation * light.intensity * light.color;
-    return output_color;
+    return blend_light(light.color, attenuation * light.intensity);
 }
@@ -1,25 +1,26 @@
-use std::f32::consts::E;
-
 use bevy::{
     asset::load_internal_asset,
     prelude::*,
     reflect::TypeUuid,
     render::{
         render_resource::{
-            BlendComponent, BlendFactor, BlendOperation, BlendState, ColorTargetState, ColorWrites,
             Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
         },
-        texture::{BevyDefault, ImageSampler},
+        texture::ImageSampler,
     },
     sprite::Material2dPlugin,
     window::PrimaryWindow,
 };
 
+pub mod blend;
+pub mod falloff;
 pub mod freeform;
 pub mod overlay;
 pub mod point;
 pub mod sprite;
 
+pub use blend::*;
+pub use falloff::*;
 pub use freeform::*;
 pub use overlay::*;
 pub use point::*;
@@ -34,6 +35,9 @@ const LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE: HandleUntyped =
 const LIGHT2D_CIRCLE_LOOKUP_IMAGE_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Image::TYPE_UUID, 7954851330280344899);
 
+const LIGHT2D_BLEND_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 10613938436618322461);
+
 const LIGHT2D_OVERLAY_MATERIAL_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 3333834159522335299);
 
@@ -50,6 +54,13 @@ pub struct Light2dPlugin;
 
 impl Plugin for Light2dPlugin {
     fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            LIGHT2D_BLEND_SHADER_HANDLE,
+            "blend.wgsl",
+            Shader::from_wgsl
+        );
+
         load_internal_asset!(
             app,
             LIGHT2D_OVERLAY_MATERIAL_SHADER_HANDLE,
@@ -82,8 +93,10 @@ impl Plugin for Light2dPlugin {
             .add_plugins(Material2dPlugin::<Light2dSpriteMaterial>::default())
             .add_plugins(Material2dPlugin::<Light2dPointMaterial>::default())
             .add_plugins(Material2dPlugin::<Light2dFreeformMaterial>::default())
+            .init_resource::<Light2dFalloffCurves>()
+            .init_resource::<Light2dPostProcessSettings>()
             .add_systems(Startup, setup_default_assets)
-            .add_systems(Update, resize_render_targets);
+            .add_systems(Update, (update_falloff_lookup_image, resize_render_targets));
     }
 }
 
@@ -93,11 +106,6 @@ fn setup_default_assets(mut meshes: ResMut<Assets<Mesh>>, mut images: ResMut<Ass
         Mesh::from(shape::Quad::default()),
     );
 
-    images.set_untracked(
-        LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE,
-        create_falloff_lookup_image(),
-    );
-
     images.set_untracked(
         LIGHT2D_CIRCLE_LOOKUP_IMAGE_HANDLE,
         create_circle_lookup_image(),
@@ -142,10 +150,14 @@ fn resize_render_targets(
     }
 }
 
-pub fn spawn_render_target_image(images: &mut Assets<Image>) -> Handle<Image> {
+pub fn spawn_render_target_image(
+    images: &mut Assets<Image>,
+    size: UVec2,
+    format: TextureFormat,
+) -> Handle<Image> {
     let size = Extent3d {
-        width: 960,
-        height: 540,
+        width: size.x,
+        height: size.y,
         ..default()
     };
     let mut overlay_image = Image {
@@ -153,7 +165,7 @@ pub fn spawn_render_target_image(images: &mut Assets<Image>) -> Handle<Image> {
             label: None,
             size,
             dimension: TextureDimension::D2,
-            format: TextureFormat::Bgra8UnormSrgb,
+            format,
             mip_level_count: 1,
             sample_count: 1,
             usage: TextureUsages::TEXTURE_BINDING
@@ -168,34 +180,6 @@ pub fn spawn_render_target_image(images: &mut Assets<Image>) -> Handle<Image> {
     images.add(overlay_image)
 }
 
-fn create_falloff_lookup_image() -> Image {
-    const WIDTH: usize = 2048;
-    const HEIGHT: usize = 128;
-    let mut data = Vec::with_capacity(WIDTH * HEIGHT * 4);
-    for y in 0..HEIGHT {
-        for x in 0..WIDTH {
-            let alpha: f32 = x as f32 / WIDTH as f32;
-            let intensity: f32 = y as f32 / HEIGHT as f32;
-            let falloff = alpha.powf(E.powf(1.5 - 3.0 * intensity));
-            for u in falloff.to_bits().to_le_bytes() {
-                data.push(u);
-            }
-        }
-    }
-    let mut image = Image::new_fill(
-        Extent3d {
-            width: WIDTH as u32,
-            height: HEIGHT as u32,
-            depth_or_array_layers: 1,
-        },
-        TextureDimension::D2,
-        &data[..],
-        TextureFormat::R32Float,
-    );
-    image.sampler_descriptor = ImageSampler::Descriptor(ImageSampler::linear_descriptor());
-    image
-}
-
 fn create_circle_lookup_image() -> Image {
     const WIDTH: usize = 256;
     const HEIGHT: usize = 256;
@@ -240,18 +224,3 @@ fn create_circle_lookup_image() -> Image {
     image.sampler_descriptor = ImageSampler::Descriptor(ImageSampler::linear_descriptor());
     image
 }
-
-fn create_light2d_fragment_target() -> ColorTargetState {
-    ColorTargetState {
-        format: TextureFormat::bevy_default(),
-        blend: Some(BlendState {
-            color: BlendComponent {
-                src_factor: BlendFactor::One,
-                dst_factor: BlendFactor::One,
-                operation: BlendOperation::Add,
-            },
-            alpha: BlendComponent::REPLACE,
-        }),
-        write_mask: ColorWrites::ALL,
-    }
-}
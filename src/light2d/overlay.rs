@@ -1,14 +1,20 @@
 use bevy::{
     prelude::*,
     reflect::TypeUuid,
-    render::render_resource::{AsBindGroup, ShaderRef},
-    sprite::Material2d,
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+    sprite::{Material2d, Material2dKey},
 };
 
 use super::LIGHT2D_OVERLAY_MATERIAL_SHADER_HANDLE;
 
-#[derive(AsBindGroup, Reflect, Debug, Clone, TypeUuid)]
+#[derive(AsBindGroup, Reflect, Debug, Clone, PartialEq, TypeUuid)]
 #[reflect(Debug)]
+#[bind_group_data(Light2dOverlayMaterialKey)]
 #[uuid = "509e4cd3-d94a-4e4d-8cc3-471a4f10da6d"]
 pub struct Light2dOverlayMaterial {
     #[texture(0)]
@@ -17,10 +23,126 @@ pub struct Light2dOverlayMaterial {
     #[texture(2)]
     #[sampler(3)]
     pub light: Handle<Image>,
+    /// Multiplier applied to the light buffer before tone mapping.
+    #[uniform(4)]
+    pub exposure: f32,
+    /// Light level mapped to full brightness. `1.0` simply clamps the light buffer.
+    #[uniform(4)]
+    pub white_point: f32,
+    /// Screen space position the light shafts radiate from.
+    #[uniform(4)]
+    pub god_rays_origin: Vec2,
+    #[uniform(4)]
+    pub god_rays_intensity: f32,
+    #[uniform(4)]
+    pub god_rays_decay: f32,
+    #[uniform(4)]
+    pub vignette_intensity: f32,
+    #[uniform(4)]
+    pub vignette_radius: f32,
+    /// Part of the render targets covered by the camera viewports.
+    #[uniform(4)]
+    pub uv_scale: Vec2,
+    pub god_rays: bool,
+    pub vignette: bool,
+    /// Shows the tone mapped light buffer instead of the lit scene.
+    pub light_only: bool,
+}
+
+impl Default for Light2dOverlayMaterial {
+    fn default() -> Self {
+        Self {
+            main: Default::default(),
+            light: Default::default(),
+            exposure: 1.0,
+            white_point: 1.0,
+            god_rays_origin: Vec2::new(0.5, 0.0),
+            god_rays_intensity: 0.0,
+            god_rays_decay: 1.0,
+            vignette_intensity: 0.0,
+            vignette_radius: 1.0,
+            uv_scale: Vec2::ONE,
+            god_rays: false,
+            vignette: false,
+            light_only: false,
+        }
+    }
 }
 
 impl Material2d for Light2dOverlayMaterial {
     fn fragment_shader() -> ShaderRef {
         LIGHT2D_OVERLAY_MATERIAL_SHADER_HANDLE.typed().into()
     }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _: &MeshVertexBufferLayout,
+        key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        if let Some(ref mut fragment) = descriptor.fragment {
+            if key.bind_group_data.god_rays {
+                fragment.shader_defs.push("GOD_RAYS".into());
+            }
+            if key.bind_group_data.vignette {
+                fragment.shader_defs.push("VIGNETTE".into());
+            }
+            if key.bind_group_data.light_only {
+                fragment.shader_defs.push("LIGHT_ONLY".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Light2dOverlayMaterialKey {
+    pub god_rays: bool,
+    pub vignette: bool,
+    pub light_only: bool,
+}
+
+impl From<&Light2dOverlayMaterial> for Light2dOverlayMaterialKey {
+    fn from(material: &Light2dOverlayMaterial) -> Self {
+        Self {
+            god_rays: material.god_rays,
+            vignette: material.vignette,
+            light_only: material.light_only,
+        }
+    }
+}
+
+/// Post-processing applied when the light buffer is composited.
+#[derive(Resource)]
+pub struct Light2dPostProcessSettings {
+    pub god_rays: bool,
+    /// World position the light shafts radiate from.
+    pub god_rays_origin: Vec2,
+    pub god_rays_intensity: f32,
+    /// Fraction of the light kept at each step away from the shaded pixel.
+    pub god_rays_decay: f32,
+    pub bloom: bool,
+    pub bloom_intensity: f32,
+    /// Brightness above which pixels start to bloom.
+    pub bloom_threshold: f32,
+    pub vignette: bool,
+    pub vignette_intensity: f32,
+    /// Distance from the screen center, in uv, where the vignette starts.
+    pub vignette_radius: f32,
+}
+
+impl Default for Light2dPostProcessSettings {
+    fn default() -> Self {
+        Self {
+            god_rays: false,
+            god_rays_origin: Vec2::new(0.0, 500.0),
+            god_rays_intensity: 0.4,
+            god_rays_decay: 0.96,
+            bloom: false,
+            bloom_intensity: 0.2,
+            bloom_threshold: 0.8,
+            vignette: false,
+            vignette_intensity: 0.6,
+            vignette_radius: 0.4,
+        }
+    }
 }
@@ -10,12 +10,66 @@ var light: texture_2d<f32>;
 @group(1) @binding(3)
 var light_sampler: sampler;
 
+struct Overlay {
+    exposure: f32,
+    white_point: f32,
+    god_rays_origin: vec2<f32>,
+    god_rays_intensity: f32,
+    god_rays_decay: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    uv_scale: vec2<f32>,
+}
+
+@group(1) @binding(4)
+var<uniform> overlay: Overlay;
+
+// Extended Reinhard, maps `white_point` to 1.0.
+fn tone_mapping(color: vec3<f32>) -> vec3<f32> {
+    let white_squared = overlay.white_point * overlay.white_point;
+    return saturate(color * (1.0 + color / white_squared) / (1.0 + color));
+}
+
+#ifdef GOD_RAYS
+const GOD_RAYS_SAMPLES: i32 = 32;
+
+// Radial blur of the light buffer toward the origin of the light shafts.
+fn god_rays(uv: vec2<f32>) -> vec3<f32> {
+    let delta = (uv - overlay.god_rays_origin * overlay.uv_scale) / f32(GOD_RAYS_SAMPLES);
+    var sample_uv = uv;
+    var weight = 1.0;
+    var sum = vec3<f32>(0.0);
+    for (var i = 0; i < GOD_RAYS_SAMPLES; i++) {
+        sample_uv -= delta;
+        // Stays within the viewport, the rest of the light buffer is stale.
+        let clamped_uv = clamp(sample_uv, vec2<f32>(0.0), overlay.uv_scale);
+        sum += textureSampleLevel(light, light_sampler, clamped_uv, 0.0).rgb * weight;
+        weight *= overlay.god_rays_decay;
+    }
+    return sum * overlay.god_rays_intensity / f32(GOD_RAYS_SAMPLES);
+}
+#endif
+
 @fragment
 fn fragment(
     mesh: MeshVertexOutput,
 ) -> @location(0) vec4<f32> {
-    let main_color = textureSample(main, main_sampler, mesh.uv);
-    let light_color = textureSample(light, light_sampler, mesh.uv);
-    let color = vec4<f32>(light_color.rgb * main_color.rgb, main_color.a);
+    let uv = mesh.uv * overlay.uv_scale;
+    let main_color = textureSample(main, main_sampler, uv);
+    var light_color = max(textureSample(light, light_sampler, uv).rgb, vec3<f32>(0.0));
+#ifdef GOD_RAYS
+    light_color += max(god_rays(uv), vec3<f32>(0.0));
+#endif
+    let light_mapped = tone_mapping(light_color * overlay.exposure);
+#ifdef LIGHT_ONLY
+    var color = vec4<f32>(light_mapped, 1.0);
+#else
+    var color = vec4<f32>(light_mapped * main_color.rgb, main_color.a);
+#endif
+#ifdef VIGNETTE
+    let distance = length(mesh.uv - vec2<f32>(0.5));
+    let vignette = smoothstep(overlay.vignette_radius, overlay.vignette_radius + 0.5, distance);
+    color = vec4<f32>(color.rgb * (1.0 - vignette * overlay.vignette_intensity), color.a);
+#endif
     return color;
 }
@@ -13,21 +13,24 @@ use bevy::{
 };
 
 use super::{
-    create_light2d_fragment_target, LIGHT2D_CIRCLE_LOOKUP_IMAGE_HANDLE,
-    LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE, LIGHT2D_POINT_MATERIAL_SHADER_HANDLE,
+    specialize_light2d, Light2dBlendMode, Light2dFalloff, Light2dMaterialKey,
+    LIGHT2D_CIRCLE_LOOKUP_IMAGE_HANDLE, LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE,
+    LIGHT2D_POINT_MATERIAL_SHADER_HANDLE,
 };
 
 #[derive(AsBindGroup, Reflect, Debug, Clone, TypeUuid)]
 #[reflect(Debug)]
+#[bind_group_data(Light2dMaterialKey)]
 #[uuid = "44e7385a-1ea0-4785-b6f9-191b99dd2cca"]
 #[uniform(0, Light2dPointMaterialUniform)]
 pub struct Light2dPointMaterial {
     pub color: Color,
     pub intensity: f32,
-    pub falloff: f32,
+    pub falloff: Light2dFalloff,
     pub inner_angle: f32,
     pub outer_angle: f32,
     pub inner_radius: f32,
+    pub blend_mode: Light2dBlendMode,
     #[texture(1)]
     #[sampler(2)]
     pub falloff_lookup: Handle<Image>,
@@ -41,10 +44,11 @@ impl Default for Light2dPointMaterial {
         Self {
             color: Color::WHITE,
             intensity: 1.0,
-            falloff: 0.5,
+            falloff: Light2dFalloff::default(),
             inner_angle: 1.0,
             outer_angle: 1.0,
             inner_radius: 0.0,
+            blend_mode: Light2dBlendMode::Additive,
             falloff_lookup: LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE.clone().typed(),
             circle_lookup: LIGHT2D_CIRCLE_LOOKUP_IMAGE_HANDLE.clone().typed(),
         }
@@ -70,7 +74,7 @@ impl AsBindGroupShaderType<Light2dPointMaterialUniform> for Light2dPointMaterial
         Light2dPointMaterialUniform {
             color: self.color.as_linear_rgba_f32().into(),
             intensity: self.intensity,
-            falloff: self.falloff,
+            falloff: self.falloff.lookup_coordinate(),
             outer_angle: self.outer_angle,
             inner_radius_mult: 1.0 / (1.0 - self.inner_radius),
             inner_angle_mult: 1.0 / (self.outer_angle - self.inner_angle),
@@ -87,11 +91,17 @@ impl Material2d for Light2dPointMaterial {
     fn specialize(
         descriptor: &mut RenderPipelineDescriptor,
         _: &MeshVertexBufferLayout,
-        _: Material2dKey<Self>,
+        key: Material2dKey<Self>,
     ) -> Result<(), SpecializedMeshPipelineError> {
-        if let Some(ref mut fragment) = descriptor.fragment {
-            fragment.targets = vec![Option::Some(create_light2d_fragment_target())];
-        }
+        specialize_light2d(descriptor, key.mesh_key, key.bind_group_data);
         Ok(())
     }
 }
+
+impl From<&Light2dPointMaterial> for Light2dMaterialKey {
+    fn from(material: &Light2dPointMaterial) -> Self {
+        Self {
+            blend_mode: material.blend_mode,
+        }
+    }
+}
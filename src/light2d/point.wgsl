@@ -1,4 +1,5 @@
 #import bevy_sprite::mesh2d_vertex_output  MeshVertexOutput
+#import bevy_demo::light2d_blend  blend_light
 
 struct Light {
     color: vec4<f32>,
@@ -40,6 +41,5 @@ fn fragment(
         falloff_lookup_sampler,
         vec2<f32>(attenuation, light.falloff)
     ).r;
-    var output_color = light.intensity * attenuation * light.color;
-    return output_color;
+    return blend_light(light.color, light.intensity * attenuation);
 }
@@ -3,28 +3,30 @@ use bevy::{
     reflect::TypeUuid,
     render::{
         mesh::MeshVertexBufferLayout,
+        render_asset::RenderAssets,
         render_resource::{
-            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+            AsBindGroup, AsBindGroupShaderType, RenderPipelineDescriptor, ShaderRef, ShaderType,
+            SpecializedMeshPipelineError,
         },
     },
     sprite::{Material2d, Material2dKey},
 };
 
 use super::{
-    create_light2d_fragment_target, LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE,
-    LIGHT2D_SPRITE_MATERIAL_SHADER_HANDLE,
+    specialize_light2d, Light2dBlendMode, Light2dFalloff, Light2dMaterialKey,
+    LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE, LIGHT2D_SPRITE_MATERIAL_SHADER_HANDLE,
 };
 
 #[derive(AsBindGroup, Reflect, Debug, Clone, TypeUuid)]
 #[reflect(Debug)]
+#[bind_group_data(Light2dMaterialKey)]
+#[uniform(0, Light2dSpriteMaterialUniform)]
 #[uuid = "26804bff-0161-4142-8f73-f810150f1f9c"]
 pub struct Light2dSpriteMaterial {
-    #[uniform(0)]
     pub color: Color,
-    #[uniform(0)]
     pub intensity: f32,
-    #[uniform(0)]
-    pub falloff: f32,
+    pub falloff: Light2dFalloff,
+    pub blend_mode: Light2dBlendMode,
     #[texture(1)]
     #[sampler(2)]
     pub sprite: Handle<Image>,
@@ -38,13 +40,34 @@ impl Default for Light2dSpriteMaterial {
         Self {
             color: Color::WHITE,
             intensity: 1.0,
-            falloff: 0.5,
+            falloff: Light2dFalloff::default(),
             sprite: Default::default(),
+            blend_mode: Light2dBlendMode::Additive,
             falloff_lookup: LIGHT2D_FALLOFF_LOOKUP_IMAGE_HANDLE.clone().typed(),
         }
     }
 }
 
+#[derive(Clone, Default, ShaderType)]
+pub struct Light2dSpriteMaterialUniform {
+    pub color: Vec4,
+    pub intensity: f32,
+    pub falloff: f32,
+}
+
+impl AsBindGroupShaderType<Light2dSpriteMaterialUniform> for Light2dSpriteMaterial {
+    fn as_bind_group_shader_type(
+        &self,
+        _images: &RenderAssets<Image>,
+    ) -> Light2dSpriteMaterialUniform {
+        Light2dSpriteMaterialUniform {
+            color: self.color.as_linear_rgba_f32().into(),
+            intensity: self.intensity,
+            falloff: self.falloff.lookup_coordinate(),
+        }
+    }
+}
+
 impl Material2d for Light2dSpriteMaterial {
     fn fragment_shader() -> ShaderRef {
         LIGHT2D_SPRITE_MATERIAL_SHADER_HANDLE.typed().into()
@@ -53,11 +76,17 @@ impl Material2d for Light2dSpriteMaterial {
     fn specialize(
         descriptor: &mut RenderPipelineDescriptor,
         _: &MeshVertexBufferLayout,
-        _: Material2dKey<Self>,
+        key: Material2dKey<Self>,
     ) -> Result<(), SpecializedMeshPipelineError> {
-        if let Some(ref mut fragment) = descriptor.fragment {
-            fragment.targets = vec![Option::Some(create_light2d_fragment_target())];
-        }
+        specialize_light2d(descriptor, key.mesh_key, key.bind_group_data);
         Ok(())
     }
 }
+
+impl From<&Light2dSpriteMaterial> for Light2dMaterialKey {
+    fn from(material: &Light2dSpriteMaterial) -> Self {
+        Self {
+            blend_mode: material.blend_mode,
+        }
+    }
+}
@@ -1,4 +1,5 @@
 #import bevy_sprite::mesh2d_vertex_output  MeshVertexOutput
+#import bevy_demo::light2d_blend  blend_light
 
 struct Light {
     color: vec4<f32>,
@@ -28,6 +29,5 @@ fn fragment(
         falloff_lookup_sampler,
         vec2<f32>(sprite_color.a, light.falloff)
     ).r;
-    let output_color = light.intensity * attenuation * light.color * sprite_color;
-    return output_color;
+    return blend_light(light.color * sprite_color, light.intensity * attenuation);
 }
@@ -2,8 +2,13 @@ use bevy::{
     asset::load_internal_asset,
     prelude::*,
     reflect::TypeUuid,
-    render::render_resource::{AsBindGroup, ShaderRef},
-    sprite::{Material2d, Material2dPlugin},
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+    sprite::{Material2d, Material2dKey, Material2dPlugin},
 };
 
 const OUTLINE_MATERIAL_SHADER_HANDLE: HandleUntyped =
@@ -28,26 +33,190 @@ impl Plugin for OutlinePlugin {
             Shader::from_wgsl
         );
 
-        app.add_plugins(Material2dPlugin::<OutlineMaterial>::default());
+        app.add_plugins(Material2dPlugin::<OutlineMaterial>::default())
+            .init_resource::<OutlineStyles>()
+            .add_systems(PostUpdate, apply_outline_styles);
     }
 }
 
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutlineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    /// Fades in from the edge over `line_width`.
+    Glow,
+}
+
 // Material
 #[derive(AsBindGroup, Reflect, Debug, Clone, TypeUuid)]
 #[reflect(Debug)]
+#[bind_group_data(OutlineMaterialKey)]
 #[uuid = "c5a092d1-e79d-4ea7-92d4-43c0798e06bf"]
 pub struct OutlineMaterial {
     #[uniform(0)]
     pub color: Color,
     #[uniform(0)]
     pub line_width: u32,
+    /// Offset and size of the shown part of the texture, in uv. A negative width mirrors it.
+    #[uniform(0)]
+    pub frame: Vec4,
+    /// Pulses per second of the outline, `0.0` keeps it steady.
+    #[uniform(0)]
+    pub pulse: f32,
+    /// Length of the dashes in texels, for `OutlineStyle::Dashed`.
+    #[uniform(0)]
+    pub dash_length: f32,
     #[texture(1)]
     #[sampler(2)]
     pub texture: Handle<Image>,
+    pub style: OutlineStyle,
+}
+
+impl OutlineMaterial {
+    pub fn new(texture: Handle<Image>) -> Self {
+        Self {
+            color: Color::WHITE,
+            line_width: 0,
+            frame: Vec4::new(0.0, 0.0, 1.0, 1.0),
+            pulse: 0.0,
+            dash_length: 2.0,
+            texture,
+            style: OutlineStyle::Solid,
+        }
+    }
 }
 
 impl Material2d for OutlineMaterial {
     fn fragment_shader() -> ShaderRef {
         OUTLINE_MATERIAL_SHADER_HANDLE.typed().into()
     }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _: &MeshVertexBufferLayout,
+        key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        if let Some(ref mut fragment) = descriptor.fragment {
+            match key.bind_group_data.style {
+                OutlineStyle::Solid => {}
+                OutlineStyle::Dashed => fragment.shader_defs.push("OUTLINE_DASHED".into()),
+                OutlineStyle::Glow => fragment.shader_defs.push("OUTLINE_GLOW".into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OutlineMaterialKey {
+    pub style: OutlineStyle,
+}
+
+impl From<&OutlineMaterial> for OutlineMaterialKey {
+    fn from(material: &OutlineMaterial) -> Self {
+        Self {
+            style: material.style,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutlineState {
+    #[default]
+    None,
+    Hovered,
+    Selected,
+    /// Hovered but not interactive.
+    Disabled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutlineLook {
+    pub color: Color,
+    pub line_width: u32,
+    pub style: OutlineStyle,
+    pub pulse: f32,
+}
+
+/// Looks of the `Outline` states.
+#[derive(Resource, Debug, Clone)]
+pub struct OutlineStyles {
+    pub hovered: OutlineLook,
+    pub selected: OutlineLook,
+    pub disabled: OutlineLook,
+}
+
+impl Default for OutlineStyles {
+    fn default() -> Self {
+        Self {
+            hovered: OutlineLook {
+                color: Color::WHITE,
+                line_width: 1,
+                style: OutlineStyle::Solid,
+                pulse: 0.0,
+            },
+            selected: OutlineLook {
+                color: Color::rgb(1.0, 0.85, 0.2),
+                line_width: 3,
+                style: OutlineStyle::Glow,
+                pulse: 1.0,
+            },
+            disabled: OutlineLook {
+                color: Color::rgba(0.6, 0.6, 0.6, 0.8),
+                line_width: 1,
+                style: OutlineStyle::Dashed,
+                pulse: 0.0,
+            },
+        }
+    }
+}
+
+impl OutlineStyles {
+    pub fn look(&self, state: OutlineState) -> Option<OutlineLook> {
+        match state {
+            OutlineState::None => None,
+            OutlineState::Hovered => Some(self.hovered),
+            OutlineState::Selected => Some(self.selected),
+            OutlineState::Disabled => Some(self.disabled),
+        }
+    }
+}
+
+/// Styles the `OutlineMaterial` of the entity by its state.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Outline {
+    pub state: OutlineState,
+}
+
+fn apply_outline_styles(
+    styles: Res<OutlineStyles>,
+    mut materials: ResMut<Assets<OutlineMaterial>>,
+    outline_query: Query<(Ref<Outline>, &Handle<OutlineMaterial>)>,
+) {
+    for (outline, material_handle) in outline_query.iter() {
+        if !outline.is_changed() && !styles.is_changed() {
+            continue;
+        }
+        let Some(material) = materials.get(material_handle) else {
+            continue;
+        };
+        let mut styled = material.clone();
+        match styles.look(outline.state) {
+            Some(look) => {
+                styled.color = look.color;
+                styled.line_width = look.line_width;
+                styled.style = look.style;
+                styled.pulse = look.pulse;
+            }
+            None => styled.line_width = 0,
+        }
+        if styled.color != material.color
+            || styled.line_width != material.line_width
+            || styled.style != material.style
+            || styled.pulse != material.pulse
+        {
+            *materials.get_mut(material_handle).unwrap() = styled;
+        }
+    }
 }
@@ -1,5 +1,5 @@
 #import bevy_sprite::mesh2d_vertex_output  MeshVertexOutput
-#import bevy_sprite::mesh2d_view_bindings  view
+#import bevy_sprite::mesh2d_view_bindings  view, globals
 
 #ifdef TONEMAP_IN_SHADER
 #import bevy_core_pipeline::tonemapping
@@ -8,6 +8,9 @@
 struct OutlineMaterial {
     color: vec4<f32>,
     line_width: u32,
+    frame: vec4<f32>,
+    pulse: f32,
+    dash_length: f32,
 };
 
 @group(1) @binding(0)
@@ -17,31 +20,59 @@ var texture: texture_2d<f32>;
 @group(1) @binding(2)
 var texture_sampler: sampler;
 
+const TAU: f32 = 6.28318530718;
+
+// Alpha of the texture, transparent outside of the frame instead of showing its neighbours.
+fn frame_alpha(uv: vec2<f32>) -> f32 {
+    let frame_min = min(outline.frame.xy, outline.frame.xy + outline.frame.zw);
+    let frame_max = max(outline.frame.xy, outline.frame.xy + outline.frame.zw);
+    let inside = all(uv >= frame_min) && all(uv <= frame_max);
+    return select(0.0, textureSampleLevel(texture, texture_sampler, uv, 0.0).a, inside);
+}
+
+// Distance in texels to the closest transparent texel, 0 when further than the line width.
+fn edge_distance(uv: vec2<f32>, texel: vec2<f32>) -> u32 {
+    for (var distance = 1u; distance <= outline.line_width; distance++) {
+        let offset = texel * f32(distance);
+        let a =
+            frame_alpha(uv + vec2f(0.0, offset.y)) *
+            frame_alpha(uv - vec2f(0.0, offset.y)) *
+            frame_alpha(uv - vec2f(offset.x, 0.0)) *
+            frame_alpha(uv + vec2f(offset.x, 0.0));
+        if (a == 0.0) {
+            return distance;
+        }
+    }
+    return 0u;
+}
+
 @fragment
 fn fragment(
     mesh: MeshVertexOutput,
 ) -> @location(0) vec4<f32> {
     let dims = vec2f(textureDimensions(texture));
+    let uv = outline.frame.xy + mesh.uv * outline.frame.zw;
 
-    let uv_up = mesh.uv + vec2f(0.0, 1.0) * f32(outline.line_width) / dims;
-    let uv_down = mesh.uv + vec2f(0.0, -1.0) * f32(outline.line_width) / dims;
-    let uv_left = mesh.uv + vec2f(-1.0, 0.0) * f32(outline.line_width) / dims;
-    let uv_right = mesh.uv + vec2f(1.0, 0.0) * f32(outline.line_width) / dims;
-
-    let a =
-        textureSample(texture, texture_sampler, uv_up).a *
-        textureSample(texture, texture_sampler, uv_down).a *
-        textureSample(texture, texture_sampler, uv_left).a *
-        textureSample(texture, texture_sampler, uv_right).a;
-
-    var output_color = textureSample(texture, texture_sampler, mesh.uv);
-    if (output_color.a != 0.0 && a == 0.0) {
-        output_color = outline.color;
+    var output_color = textureSample(texture, texture_sampler, uv);
+    let distance = edge_distance(uv, 1.0 / dims);
+    if (output_color.a != 0.0 && distance > 0u) {
+        var strength = 1.0;
+#ifdef OUTLINE_GLOW
+        strength = 1.0 - f32(distance - 1u) / f32(outline.line_width);
+#endif
+#ifdef OUTLINE_DASHED
+        let pixel = floor(uv * dims);
+        let dash = fract((pixel.x + pixel.y) / (2.0 * max(outline.dash_length, 1.0)));
+        strength *= select(0.0, 1.0, dash < 0.5);
+#endif
+        if (outline.pulse > 0.0) {
+            strength *= 0.6 + 0.4 * cos(globals.time * outline.pulse * TAU);
+        }
+        let color = mix(output_color.rgb, outline.color.rgb, strength * outline.color.a);
+        output_color = vec4<f32>(color, output_color.a);
     }
 #ifdef TONEMAP_IN_SHADER
     output_color = bevy_core_pipeline::tonemapping::tone_mapping(output_color, view.color_grading);
 #endif
     return output_color;
-    // return outline.color;
 }
-
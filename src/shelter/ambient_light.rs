@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+
+use crate::{GameDateTime, Light2dFreeformMaterial, Room, Weather};
+
+#[derive(Resource)]
+pub struct AmbientLightSettings {
+    pub outdoor_day: f32,
+    pub outdoor_night: f32,
+    pub indoor_day: f32,
+    pub indoor_night: f32,
+}
+
+impl Default for AmbientLightSettings {
+    fn default() -> Self {
+        Self {
+            outdoor_day: 1.1,
+            outdoor_night: 0.1,
+            indoor_day: 0.4,
+            indoor_night: 0.3,
+        }
+    }
+}
+
+/// Current ambient light level, updated from the day cycle.
+#[derive(Resource, Default)]
+pub struct AmbientLightLevel {
+    pub outdoor: f32,
+    pub indoor: f32,
+}
+
+#[derive(Component)]
+pub enum AmbientLightVolume {
+    /// Sky light over the surface.
+    Outdoor,
+    /// Base light shared by every interior space.
+    Indoor,
+    /// Outdoor light bleeding through an opening, scaled by `bleed`.
+    Opening { bleed: f32 },
+    /// Light of a room, following its kind and power state.
+    Room(Entity),
+}
+
+pub fn update_ambient_light(
+    game_date_time: Res<GameDateTime>,
+    settings: Res<AmbientLightSettings>,
+    weather: Res<Weather>,
+    mut ambient_light: ResMut<AmbientLightLevel>,
+    mut materials: ResMut<Assets<Light2dFreeformMaterial>>,
+    light_query: Query<(&AmbientLightVolume, &Handle<Light2dFreeformMaterial>)>,
+    room_query: Query<&Room>,
+) {
+    let ratio = game_date_time.cos();
+    ambient_light.outdoor = (settings.outdoor_night
+        + (settings.outdoor_day - settings.outdoor_night) * ratio)
+        * weather.outdoor_light();
+    ambient_light.indoor =
+        settings.indoor_night + (settings.indoor_day - settings.indoor_night) * ratio;
+
+    for (volume, material_handle) in light_query.iter() {
+        let (intensity, color) = match volume {
+            AmbientLightVolume::Outdoor => (ambient_light.outdoor, None),
+            AmbientLightVolume::Indoor => (ambient_light.indoor, None),
+            AmbientLightVolume::Opening { bleed } => (ambient_light.outdoor * bleed, None),
+            AmbientLightVolume::Room(room) => match room_query.get(*room) {
+                Ok(room) if room.powered => (
+                    room.kind.ambient_intensity(),
+                    Some(room.kind.ambient_color()),
+                ),
+                _ => (0.0, None),
+            },
+        };
+
+        let Some(material) = materials.get(material_handle) else {
+            continue;
+        };
+        if material.intensity != intensity || color.is_some_and(|color| material.color != color) {
+            let material = materials.get_mut(material_handle).unwrap();
+            material.intensity = intensity;
+            if let Some(color) = color {
+                material.color = color;
+            }
+        }
+    }
+}
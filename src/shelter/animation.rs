@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+
+use crate::{Facing, MoveMode, Moveable, OutlineMaterial};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PersonAnimationState {
+    #[default]
+    Idle,
+    Walk,
+    Climb,
+    Fall,
+}
+
+/// Frames of a sprite sheet played in a loop, counted row by row.
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationClip {
+    pub first: u32,
+    pub frames: u32,
+    pub fps: f32,
+}
+
+impl AnimationClip {
+    pub fn new(first: u32, frames: u32, fps: f32) -> Self {
+        Self { first, frames, fps }
+    }
+}
+
+/// Plays the sprite sheet of a `Person` from the state of its `Moveable` parent.
+#[derive(Component, Debug, Clone)]
+pub struct PersonAnimation {
+    /// Columns and rows of the sprite sheet.
+    pub grid: UVec2,
+    pub idle: AnimationClip,
+    pub walk: AnimationClip,
+    pub climb: AnimationClip,
+    pub fall: AnimationClip,
+    pub state: PersonAnimationState,
+    /// Mirrors the frames, which face right in the sheet.
+    pub flip: bool,
+    elapsed: f32,
+}
+
+impl Default for PersonAnimation {
+    fn default() -> Self {
+        // Layout of `demo/person_sheet.png`.
+        Self {
+            grid: UVec2::new(4, 4),
+            idle: AnimationClip::new(0, 2, 2.0),
+            walk: AnimationClip::new(4, 4, 8.0),
+            climb: AnimationClip::new(8, 2, 6.0),
+            fall: AnimationClip::new(12, 1, 1.0),
+            state: PersonAnimationState::Idle,
+            flip: false,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl PersonAnimation {
+    pub fn clip(&self, state: PersonAnimationState) -> AnimationClip {
+        match state {
+            PersonAnimationState::Idle => self.idle,
+            PersonAnimationState::Walk => self.walk,
+            PersonAnimationState::Climb => self.climb,
+            PersonAnimationState::Fall => self.fall,
+        }
+    }
+
+    /// Returns the offset and size of the current frame, in uv. Flipped frames have a negative
+    /// width.
+    pub fn frame(&self) -> Vec4 {
+        let grid = self.grid.max(UVec2::ONE);
+        let clip = self.clip(self.state);
+        let index = clip.first + (self.elapsed * clip.fps) as u32 % clip.frames.max(1);
+        let size = 1.0 / grid.as_vec2();
+        let offset = UVec2::new(index % grid.x, index / grid.x).as_vec2() * size;
+        if self.flip {
+            Vec4::new(offset.x + size.x, offset.y, -size.x, size.y)
+        } else {
+            Vec4::new(offset.x, offset.y, size.x, size.y)
+        }
+    }
+}
+
+// Horizontal speed above which a person walks.
+const WALK_SPEED: f32 = 5.0;
+
+pub fn update_person_animation(
+    time: Res<Time>,
+    mut materials: ResMut<Assets<OutlineMaterial>>,
+    moveable_query: Query<(&Moveable, &Velocity)>,
+    mut person_query: Query<(&Parent, &mut PersonAnimation, &Handle<OutlineMaterial>)>,
+) {
+    for (parent, mut animation, material_handle) in person_query.iter_mut() {
+        let Ok((moveable, velocity)) = moveable_query.get(parent.get()) else {
+            continue;
+        };
+        let state = match moveable.mode {
+            MoveMode::InStair { .. } => PersonAnimationState::Climb,
+            MoveMode::Falling { .. } => PersonAnimationState::Fall,
+            MoveMode::Landing { .. } => PersonAnimationState::Idle,
+            MoveMode::Normal if velocity.linvel.x.abs() > WALK_SPEED => PersonAnimationState::Walk,
+            MoveMode::Normal => PersonAnimationState::Idle,
+        };
+        if animation.state != state {
+            animation.state = state;
+            animation.elapsed = 0.0;
+        }
+        animation.elapsed += time.delta_seconds();
+        animation.flip = moveable.facing == Facing::Left;
+
+        let frame = animation.frame();
+        if materials
+            .get(material_handle)
+            .is_some_and(|material| material.frame != frame)
+        {
+            materials.get_mut(material_handle).unwrap().frame = frame;
+        }
+    }
+}
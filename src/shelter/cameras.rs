@@ -1,16 +1,40 @@
 use bevy::{
-    core_pipeline::clear_color::ClearColorConfig,
+    core_pipeline::{
+        bloom::{BloomPrefilterSettings, BloomSettings},
+        clear_color::ClearColorConfig,
+    },
     prelude::*,
-    render::{camera::RenderTarget, view::RenderLayers},
+    render::{
+        camera::RenderTarget,
+        camera::Viewport,
+        render_resource::{FilterMode, TextureFormat},
+        texture::ImageSampler,
+        view::{RenderLayers, ViewTarget},
+    },
     sprite::MaterialMesh2dBundle,
+    window::PrimaryWindow,
 };
 
-use crate::{spawn_render_target_image, BackgroundCamera, Light2dOverlayMaterial};
+use crate::{
+    spawn_render_target_image, BackgroundCamera, Light2dOverlayMaterial,
+    Light2dPostProcessSettings, PixelCamera,
+};
 
 pub const RENDER_LAYER_MAIN1: RenderLayers = RenderLayers::layer(0);
 pub const RENDER_LAYER_MAIN2: RenderLayers = RenderLayers::layer(1);
 pub const RENDER_LAYER_LIGHT1: RenderLayers = RenderLayers::layer(2);
 pub const RENDER_LAYER_MERGE1: RenderLayers = RenderLayers::layer(3);
+/// Drawn over the lit scene, e.g. unlit foreground parallax layers.
+pub const RENDER_LAYER_FOREGROUND: RenderLayers = RenderLayers::layer(4);
+/// Seen by the main camera only, e.g. background stacks sized to its view.
+pub const RENDER_LAYER_MAIN_ONLY: RenderLayers = RenderLayers::layer(5);
+/// Drawn on the minimap only, e.g. markers.
+pub const RENDER_LAYER_MINIMAP: RenderLayers = RenderLayers::layer(6);
+
+/// Layers seen by a camera showing both `a` and `b`.
+pub fn render_layers_union(a: RenderLayers, b: RenderLayers) -> RenderLayers {
+    b.iter().fold(a, RenderLayers::with)
+}
 
 #[derive(Component)]
 pub struct MainCamera;
@@ -18,6 +42,17 @@ pub struct MainCamera;
 #[derive(Component)]
 pub struct LightCamera;
 
+#[derive(Component)]
+pub struct MergeCamera;
+
+/// Child of the main camera drawing straight to the window.
+#[derive(Component)]
+pub struct ScreenCamera;
+
+/// Screen camera drawing `RENDER_LAYER_FOREGROUND` over the lit scene.
+#[derive(Component)]
+pub struct ForegroundCamera;
+
 pub fn setup_cameras(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -30,12 +65,21 @@ pub fn setup_cameras(
                 clear_color: ClearColorConfig::None,
                 ..default()
             },
+            camera: Camera {
+                hdr: true,
+                ..default()
+            },
             ..default()
         },
         RENDER_LAYER_MERGE1,
+        MergeCamera,
     ));
 
-    let main_texture = spawn_render_target_image(&mut images);
+    let main_texture = spawn_render_target_image(
+        &mut images,
+        UVec2::new(960, 540),
+        TextureFormat::Bgra8UnormSrgb,
+    );
     let camera_main = commands
         .spawn((
             Camera2dBundle {
@@ -50,13 +94,17 @@ pub fn setup_cameras(
                 },
                 ..default()
             },
-            RENDER_LAYER_MAIN1,
+            render_layers_union(RENDER_LAYER_MAIN1, RENDER_LAYER_MAIN_ONLY),
             MainCamera,
             BackgroundCamera,
         ))
         .id();
 
-    let light_texture = spawn_render_target_image(&mut images);
+    let light_texture = spawn_render_target_image(
+        &mut images,
+        UVec2::new(960, 540),
+        ViewTarget::TEXTURE_FORMAT_HDR,
+    );
     let camera_light = commands
         .spawn((
             Camera2dBundle {
@@ -67,6 +115,7 @@ pub fn setup_cameras(
                 camera: Camera {
                     order: -1,
                     target: RenderTarget::Image(light_texture.clone()),
+                    hdr: true,
                     ..default()
                 },
                 transform: Transform::from_xyz(0.0, 0.0, 0.0),
@@ -82,18 +131,42 @@ pub fn setup_cameras(
             Camera2dBundle {
                 camera: Camera {
                     order: -1,
+                    hdr: true,
                     ..default()
                 },
                 transform: Transform::from_xyz(0.0, 0.0, 0.0),
                 ..default()
             },
             RENDER_LAYER_MAIN2,
+            ScreenCamera,
+        ))
+        .id();
+
+    let camera_foreground = commands
+        .spawn((
+            Camera2dBundle {
+                camera_2d: Camera2d {
+                    clear_color: ClearColorConfig::None,
+                },
+                camera: Camera {
+                    order: 1,
+                    hdr: true,
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, 0.0),
+                ..default()
+            },
+            RENDER_LAYER_FOREGROUND,
+            ScreenCamera,
+            ForegroundCamera,
         ))
         .id();
 
-    commands
-        .entity(camera_main)
-        .push_children(&[camera_light, camera_background]);
+    commands.entity(camera_main).push_children(&[
+        camera_light,
+        camera_background,
+        camera_foreground,
+    ]);
 
     let mesh = meshes.add(Mesh::from(shape::Quad::default()));
     commands.spawn((
@@ -102,6 +175,7 @@ pub fn setup_cameras(
             material: overlay_materials.add(Light2dOverlayMaterial {
                 main: main_texture,
                 light: light_texture,
+                ..default()
             }),
             transform: Transform {
                 scale: Vec3::new(960.0, 540.0, 1.0),
@@ -112,3 +186,152 @@ pub fn setup_cameras(
         RENDER_LAYER_MERGE1,
     ));
 }
+
+pub fn update_post_process(
+    mut commands: Commands,
+    settings: Res<Light2dPostProcessSettings>,
+    mut overlay_materials: ResMut<Assets<Light2dOverlayMaterial>>,
+    overlay_query: Query<&Handle<Light2dOverlayMaterial>>,
+    main_camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    merge_camera_query: Query<(Entity, Option<&BloomSettings>), With<MergeCamera>>,
+) {
+    let (camera, camera_transform) = main_camera_query.single();
+    let god_rays_origin = camera
+        .world_to_viewport(camera_transform, settings.god_rays_origin.extend(0.0))
+        .zip(camera.logical_viewport_size())
+        .map(|(position, size)| position / size);
+
+    for material_handle in overlay_query.iter() {
+        let Some(material) = overlay_materials.get(material_handle) else {
+            continue;
+        };
+        let mut overlay = material.clone();
+        overlay.god_rays = settings.god_rays && god_rays_origin.is_some();
+        overlay.god_rays_origin = god_rays_origin.unwrap_or(overlay.god_rays_origin);
+        overlay.god_rays_intensity = settings.god_rays_intensity;
+        overlay.god_rays_decay = settings.god_rays_decay;
+        overlay.vignette = settings.vignette;
+        overlay.vignette_intensity = settings.vignette_intensity;
+        overlay.vignette_radius = settings.vignette_radius;
+
+        if overlay != *material {
+            *overlay_materials.get_mut(material_handle).unwrap() = overlay;
+        }
+    }
+
+    for (camera, bloom) in merge_camera_query.iter() {
+        if !settings.bloom {
+            if bloom.is_some() {
+                commands.entity(camera).remove::<BloomSettings>();
+            }
+            continue;
+        }
+        // `Option::is_none_or` is newer than the toolchains Bevy 0.11 supports.
+        #[allow(clippy::unnecessary_map_or)]
+        let changed = bloom.map_or(true, |bloom| {
+            bloom.intensity != settings.bloom_intensity
+                || bloom.prefilter_settings.threshold != settings.bloom_threshold
+        });
+        if changed {
+            commands.entity(camera).insert(BloomSettings {
+                intensity: settings.bloom_intensity,
+                prefilter_settings: BloomPrefilterSettings {
+                    threshold: settings.bloom_threshold,
+                    threshold_softness: 0.2,
+                },
+                ..BloomSettings::OLD_SCHOOL
+            });
+        }
+    }
+}
+
+type PixelCameraFilter = Or<(With<MainCamera>, With<LightCamera>)>;
+type ScreenCameraFilter = (With<ScreenCamera>, Without<Handle<Light2dOverlayMaterial>>);
+
+/// Renders the main and light cameras to a low resolution viewport of their render targets, which
+/// the overlay upscales by the `PixelCamera` zoom and offsets by the snapping remainder.
+pub fn update_pixel_camera(
+    pixel_camera: Res<PixelCamera>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut images: ResMut<Assets<Image>>,
+    mut overlay_materials: ResMut<Assets<Light2dOverlayMaterial>>,
+    mut overlay_query: Query<(&Handle<Light2dOverlayMaterial>, &mut Transform)>,
+    mut camera_query: Query<&mut Camera, PixelCameraFilter>,
+    mut screen_camera_query: Query<&mut Transform, ScreenCameraFilter>,
+) {
+    let window = window_query.single();
+    // Logical pixels, like the screen cameras showing the overlay and the cursor.
+    let window_size = Vec2::new(window.width(), window.height()).ceil().as_uvec2();
+    let zoom = pixel_camera.zoom.max(1);
+
+    for (material_handle, mut transform) in overlay_query.iter_mut() {
+        let Some(material) = overlay_materials.get(material_handle) else {
+            continue;
+        };
+        let Some(target_size) = images.get(&material.main).map(|image| image.size()) else {
+            continue;
+        };
+
+        // One texel of padding on each side hides the edges moved in by the remainder. An even
+        // size keeps the snapped camera center on a texel corner.
+        let viewport_size = if pixel_camera.enabled {
+            (((window_size + zoom - 1) / zoom + 3) / 2 * 2).min(target_size.as_uvec2())
+        } else {
+            target_size.as_uvec2()
+        };
+        let viewport = pixel_camera.enabled.then(|| Viewport {
+            physical_position: UVec2::ZERO,
+            physical_size: viewport_size,
+            ..default()
+        });
+        for mut camera in camera_query.iter_mut() {
+            let size = |viewport: &Option<Viewport>| viewport.as_ref().map(|v| v.physical_size);
+            if size(&camera.viewport) != size(&viewport) {
+                camera.viewport = viewport.clone();
+            }
+        }
+
+        let uv_scale = viewport_size.as_vec2() / target_size;
+        if material.uv_scale != uv_scale {
+            overlay_materials.get_mut(material_handle).unwrap().uv_scale = uv_scale;
+        }
+        if pixel_camera.enabled {
+            transform.scale = (viewport_size * zoom).as_vec2().extend(transform.scale.z);
+            transform.translation = pixel_camera.screen_offset().extend(transform.translation.z);
+        } else if transform.translation.truncate() != Vec2::ZERO {
+            transform.translation = Vec3::new(0.0, 0.0, transform.translation.z);
+        }
+
+        // Upscales the scene pixels without blurring them.
+        let main = overlay_materials.get(material_handle).unwrap().main.clone();
+        let nearest = images.get(&main).is_some_and(|image| {
+            matches!(&image.sampler_descriptor,
+                ImageSampler::Descriptor(descriptor) if descriptor.mag_filter == FilterMode::Nearest)
+        });
+        if nearest != pixel_camera.enabled {
+            if let Some(image) = images.get_mut(&main) {
+                image.sampler_descriptor = if pixel_camera.enabled {
+                    ImageSampler::nearest()
+                } else {
+                    ImageSampler::Descriptor(ImageSampler::linear_descriptor())
+                };
+            }
+        }
+    }
+
+    // Window cameras show the unsnapped camera at the screen scale.
+    let screen_transform = if pixel_camera.enabled {
+        Transform {
+            translation: pixel_camera.remainder.extend(0.0),
+            scale: Vec3::new(1.0 / zoom as f32, 1.0 / zoom as f32, 1.0),
+            ..default()
+        }
+    } else {
+        Transform::IDENTITY
+    };
+    for mut transform in screen_camera_query.iter_mut() {
+        if *transform != screen_transform {
+            *transform = screen_transform;
+        }
+    }
+}
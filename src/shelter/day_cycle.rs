@@ -2,7 +2,7 @@ use std::f32::consts::PI;
 
 use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*};
 
-use crate::{BackgroundMaterial, Light2dFreeformMaterial, LightCamera};
+use crate::{Action, BackgroundMaterial, BackgroundPalette, LightCamera};
 
 #[derive(Resource, Default)]
 pub struct GameDateTime {
@@ -18,13 +18,6 @@ impl GameDateTime {
     }
 }
 
-#[derive(Component)]
-pub struct LightIntensity {
-    pub max: f32,
-    pub min: f32,
-    pub addition: f32,
-}
-
 #[derive(Component)]
 pub struct GameDateTimeText;
 
@@ -52,49 +45,44 @@ pub fn day_cycle(
 }
 
 pub fn update_background_color(
-    game_date_time: ResMut<GameDateTime>,
+    game_date_time: Res<GameDateTime>,
+    palette: Res<BackgroundPalette>,
     mut materials: ResMut<Assets<BackgroundMaterial>>,
-    mut background_query: Query<&Handle<BackgroundMaterial>>,
-) {
-    let ratio = game_date_time.cos();
-    for material_handle in &mut background_query {
-        let material = materials.get_mut(material_handle).unwrap();
-        material.palette_ratio.x = ratio;
-    }
-}
-
-pub fn update_ambient_light(
-    game_date_time: ResMut<GameDateTime>,
-    mut materials: ResMut<Assets<Light2dFreeformMaterial>>,
-    light_query: Query<(&LightIntensity, &Handle<Light2dFreeformMaterial>)>,
+    background_query: Query<&Handle<BackgroundMaterial>>,
 ) {
-    let ratio = game_date_time.cos();
-    for (intensity, material_handle) in light_query.iter() {
-        let material = materials.get_mut(&material_handle).unwrap();
-        material.intensity = intensity.min + ratio * intensity.max + intensity.addition;
+    let (palette_rows, palette_ratio) = palette.sample(game_date_time.time);
+    for material_handle in &background_query {
+        let Some(material) = materials.get_mut(material_handle) else {
+            continue;
+        };
+        // Backgrounds without a palette keep their rows negative.
+        if material.palette.is_some() {
+            material.palette_rows = palette_rows;
+            material.palette_ratio = palette_ratio;
+        }
     }
 }
 
 pub fn debug_control_day_cycle(
     mut game_date_time: ResMut<GameDateTime>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
+    if actions.just_pressed(Action::PauseDayCycle) {
         game_date_time.paused = !game_date_time.paused;
     }
-    if keyboard_input.just_pressed(KeyCode::Q) {
+    if actions.just_pressed(Action::PreviousHour) {
         game_date_time.time = ((game_date_time.time * 24.0 + 23.0).floor() / 24.0).fract();
     }
-    if keyboard_input.just_pressed(KeyCode::E) {
+    if actions.just_pressed(Action::NextHour) {
         game_date_time.time = ((game_date_time.time * 24.0 + 1.0).floor() / 24.0).fract();
     }
 }
 
 pub fn debug_toggle_global_light(
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
     mut camera_query: Query<&mut Camera2d, With<LightCamera>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::L) {
+    if actions.just_pressed(Action::ToggleGlobalLight) {
         let mut camera2d = camera_query.single_mut();
         if let ClearColorConfig::Custom(color) = camera2d.clear_color {
             if color == Color::WHITE {
@@ -1,7 +1,14 @@
-use bevy::prelude::*;
+use std::f32::consts::PI;
+
+use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+use bevy_egui::{egui, EguiContexts};
 use bevy_rapier2d::render::{DebugRenderContext, RapierDebugRenderPlugin};
 
-use crate::{world_coor, Moveable, PathFinder, Stair};
+use crate::{
+    world_coor, Action, AmbientLightVolume, Light2dBlendMode, Light2dFalloff,
+    Light2dFreeformMaterial, Light2dOverlayMaterial, Light2dPointMaterial,
+    Light2dPostProcessSettings, Light2dSpriteMaterial, Moveable, PathFinder, PixelCamera, Stair,
+};
 
 pub struct DebugPlugin;
 
@@ -11,12 +18,16 @@ impl Plugin for DebugPlugin {
             .insert_resource(DebugContext {
                 should_render_components: false,
                 should_render_path_find: false,
+                should_render_lights: false,
+                should_render_light_editor: false,
             })
             .add_systems(
                 Update,
                 (
                     debug_render_components,
                     debug_render_path_find,
+                    debug_render_lights,
+                    debug_light_editor,
                     toggle_debug_context,
                 ),
             );
@@ -27,6 +38,8 @@ impl Plugin for DebugPlugin {
 struct DebugContext {
     should_render_components: bool,
     should_render_path_find: bool,
+    should_render_lights: bool,
+    should_render_light_editor: bool,
 }
 
 fn debug_render_components(
@@ -88,18 +101,304 @@ fn debug_render_path_find(
     }
 }
 
+fn debug_render_lights(
+    mut gizmos: Gizmos,
+    debug_context: Res<DebugContext>,
+    meshes: Res<Assets<Mesh>>,
+    point_materials: Res<Assets<Light2dPointMaterial>>,
+    point_query: Query<(&GlobalTransform, &Handle<Light2dPointMaterial>)>,
+    freeform_query: Query<(&GlobalTransform, &Handle<Mesh>), With<Handle<Light2dFreeformMaterial>>>,
+    sprite_query: Query<&GlobalTransform, With<Handle<Light2dSpriteMaterial>>>,
+) {
+    if !debug_context.should_render_lights {
+        return;
+    }
+
+    for (transform, material_handle) in point_query.iter() {
+        let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+        let center = translation.truncate();
+        let radius = scale.x / 2.0;
+        gizmos.rect_2d(center, 0.0, scale.truncate(), Color::YELLOW);
+        let Some(material) = point_materials.get(material_handle) else {
+            continue;
+        };
+
+        // Cones are centered on the local up direction, `1.0` covers the full circle.
+        let (_, _, angle) = rotation.to_euler(EulerRot::XYZ);
+        let mut draw_cone = |cone: f32, radius: f32, color: Color| {
+            gizmos.arc_2d(center, -angle, 2.0 * PI * cone, radius, color);
+            if cone < 1.0 {
+                for side in [-1.0, 1.0] {
+                    let direction = Vec2::from_angle(angle + side * PI * cone).rotate(Vec2::Y);
+                    gizmos.line_2d(center, center + direction * radius, color);
+                }
+            }
+        };
+        draw_cone(material.outer_angle, radius, Color::ORANGE);
+        draw_cone(material.inner_angle, radius, Color::GOLD);
+        if material.inner_radius > 0.0 {
+            draw_cone(
+                material.outer_angle,
+                radius * material.inner_radius,
+                Color::GOLD,
+            );
+        }
+    }
+
+    for (transform, mesh_handle) in freeform_query.iter() {
+        let Some(mesh) = meshes.get(mesh_handle) else {
+            continue;
+        };
+        let (
+            Some(VertexAttributeValues::Float32x3(positions)),
+            Some(VertexAttributeValues::Float32x4(colors)),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+            mesh.attribute(Mesh::ATTRIBUTE_COLOR),
+        )
+        else {
+            continue;
+        };
+
+        // Inner vertices have full attenuation, the `extend` ring fades to zero.
+        let outline = |inner: bool| {
+            let points = positions
+                .iter()
+                .zip(colors.iter())
+                .filter(|(_, color)| (color[3] > 0.0) == inner)
+                .map(|(position, _)| {
+                    transform
+                        .transform_point(Vec3::from_array(*position))
+                        .truncate()
+                })
+                .collect::<Vec<Vec2>>();
+            points.first().map(|first| {
+                let mut points = points.clone();
+                points.push(*first);
+                points
+            })
+        };
+        if let Some(points) = outline(true) {
+            gizmos.linestrip_2d(points, Color::ORANGE);
+        }
+        if let Some(points) = outline(false) {
+            gizmos.linestrip_2d(points, Color::GOLD);
+        }
+    }
+
+    for transform in sprite_query.iter() {
+        let (scale, _, translation) = transform.to_scale_rotation_translation();
+        gizmos.rect_2d(translation.truncate(), 0.0, scale.truncate(), Color::YELLOW);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct LightProperties {
+    color: Color,
+    intensity: f32,
+    falloff: Light2dFalloff,
+    blend_mode: Light2dBlendMode,
+}
+
+fn light_properties_ui(ui: &mut egui::Ui, id: impl std::hash::Hash, light: &mut LightProperties) {
+    let mut color = [light.color.r(), light.color.g(), light.color.b()];
+    ui.horizontal(|ui| {
+        ui.label("color");
+        egui::color_picker::color_edit_button_rgb(ui, &mut color);
+    });
+    light.color = Color::rgba(color[0], color[1], color[2], light.color.a());
+
+    ui.add(egui::Slider::new(&mut light.intensity, 0.0..=2.0).text("intensity"));
+
+    egui::ComboBox::from_id_source((&id, "falloff"))
+        .selected_text(match light.falloff {
+            Light2dFalloff::Exponential(_) => "Exponential".to_owned(),
+            falloff => format!("{:?}", falloff),
+        })
+        .show_ui(ui, |ui| {
+            let exponential = match light.falloff {
+                Light2dFalloff::Exponential(falloff) => falloff,
+                _ => 0.5,
+            };
+            ui.selectable_value(
+                &mut light.falloff,
+                Light2dFalloff::Exponential(exponential),
+                "Exponential",
+            );
+            ui.selectable_value(&mut light.falloff, Light2dFalloff::Linear, "Linear");
+            ui.selectable_value(
+                &mut light.falloff,
+                Light2dFalloff::InverseSquare,
+                "InverseSquare",
+            );
+        });
+    if let Light2dFalloff::Exponential(ref mut falloff) = light.falloff {
+        ui.add(egui::Slider::new(falloff, 0.0..=1.0).text("falloff"));
+    }
+
+    egui::ComboBox::from_id_source((&id, "blend_mode"))
+        .selected_text(format!("{:?}", light.blend_mode))
+        .show_ui(ui, |ui| {
+            for blend_mode in [
+                Light2dBlendMode::Additive,
+                Light2dBlendMode::Multiply,
+                Light2dBlendMode::Alpha,
+                Light2dBlendMode::Subtract,
+            ] {
+                ui.selectable_value(
+                    &mut light.blend_mode,
+                    blend_mode,
+                    format!("{:?}", blend_mode),
+                );
+            }
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn debug_light_editor(
+    mut contexts: EguiContexts,
+    debug_context: Res<DebugContext>,
+    mut freeform_materials: ResMut<Assets<Light2dFreeformMaterial>>,
+    mut point_materials: ResMut<Assets<Light2dPointMaterial>>,
+    mut sprite_materials: ResMut<Assets<Light2dSpriteMaterial>>,
+    // Ambient lights are driven by `update_ambient_light`, which would undo the edits.
+    freeform_query: Query<(Entity, &Handle<Light2dFreeformMaterial>), Without<AmbientLightVolume>>,
+    point_query: Query<(Entity, &Handle<Light2dPointMaterial>)>,
+    sprite_query: Query<(Entity, &Handle<Light2dSpriteMaterial>)>,
+) {
+    if !debug_context.should_render_light_editor {
+        return;
+    }
+
+    egui::Window::new("Lights")
+        .vscroll(true)
+        .show(contexts.ctx_mut(), |ui| {
+            for (entity, handle) in freeform_query.iter() {
+                let Some(material) = freeform_materials.get(handle) else {
+                    continue;
+                };
+                let light = LightProperties {
+                    color: material.color,
+                    intensity: material.intensity,
+                    falloff: material.falloff,
+                    blend_mode: material.blend_mode,
+                };
+                let mut edited = light;
+                ui.collapsing(format!("Freeform {:?}", entity), |ui| {
+                    light_properties_ui(ui, entity, &mut edited);
+                });
+                if edited != light {
+                    let material = freeform_materials.get_mut(handle).unwrap();
+                    material.color = edited.color;
+                    material.intensity = edited.intensity;
+                    material.falloff = edited.falloff;
+                    material.blend_mode = edited.blend_mode;
+                }
+            }
+
+            for (entity, handle) in point_query.iter() {
+                let Some(material) = point_materials.get(handle) else {
+                    continue;
+                };
+                let light = LightProperties {
+                    color: material.color,
+                    intensity: material.intensity,
+                    falloff: material.falloff,
+                    blend_mode: material.blend_mode,
+                };
+                let angles = [
+                    material.inner_angle,
+                    material.outer_angle,
+                    material.inner_radius,
+                ];
+                let mut edited = light;
+                let mut edited_angles = angles;
+                ui.collapsing(format!("Point {:?}", entity), |ui| {
+                    light_properties_ui(ui, entity, &mut edited);
+                    let [inner_angle, outer_angle, inner_radius] = &mut edited_angles;
+                    ui.add(egui::Slider::new(inner_angle, 0.0..=1.0).text("inner angle"));
+                    ui.add(egui::Slider::new(outer_angle, 0.0..=1.0).text("outer angle"));
+                    ui.add(egui::Slider::new(inner_radius, 0.0..=1.0).text("inner radius"));
+                });
+                if edited != light || edited_angles != angles {
+                    let material = point_materials.get_mut(handle).unwrap();
+                    material.color = edited.color;
+                    material.intensity = edited.intensity;
+                    material.falloff = edited.falloff;
+                    material.blend_mode = edited.blend_mode;
+                    [
+                        material.inner_angle,
+                        material.outer_angle,
+                        material.inner_radius,
+                    ] = edited_angles;
+                }
+            }
+
+            for (entity, handle) in sprite_query.iter() {
+                let Some(material) = sprite_materials.get(handle) else {
+                    continue;
+                };
+                let light = LightProperties {
+                    color: material.color,
+                    intensity: material.intensity,
+                    falloff: material.falloff,
+                    blend_mode: material.blend_mode,
+                };
+                let mut edited = light;
+                ui.collapsing(format!("Sprite {:?}", entity), |ui| {
+                    light_properties_ui(ui, entity, &mut edited);
+                });
+                if edited != light {
+                    let material = sprite_materials.get_mut(handle).unwrap();
+                    material.color = edited.color;
+                    material.intensity = edited.intensity;
+                    material.falloff = edited.falloff;
+                    material.blend_mode = edited.blend_mode;
+                }
+            }
+        });
+}
+
 fn toggle_debug_context(
     mut rapier_context: ResMut<DebugRenderContext>,
     mut debug_context: ResMut<DebugContext>,
-    keyboard_input: Res<Input<KeyCode>>,
+    mut post_process_settings: ResMut<Light2dPostProcessSettings>,
+    mut overlay_materials: ResMut<Assets<Light2dOverlayMaterial>>,
+    pixel_camera: Option<ResMut<PixelCamera>>,
+    actions: Res<Input<Action>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Key1) {
+    if actions.just_pressed(Action::DebugPhysics) {
         rapier_context.enabled = !rapier_context.enabled;
     }
-    if keyboard_input.just_pressed(KeyCode::Key2) {
+    if actions.just_pressed(Action::DebugComponents) {
         debug_context.should_render_components = !debug_context.should_render_components;
     }
-    if keyboard_input.just_pressed(KeyCode::Key3) {
+    if actions.just_pressed(Action::DebugPathFind) {
         debug_context.should_render_path_find = !debug_context.should_render_path_find;
     }
+    if actions.just_pressed(Action::DebugGodRays) {
+        post_process_settings.god_rays = !post_process_settings.god_rays;
+    }
+    if actions.just_pressed(Action::DebugBloom) {
+        post_process_settings.bloom = !post_process_settings.bloom;
+    }
+    if actions.just_pressed(Action::DebugVignette) {
+        post_process_settings.vignette = !post_process_settings.vignette;
+    }
+    if actions.just_pressed(Action::DebugLights) {
+        debug_context.should_render_lights = !debug_context.should_render_lights;
+    }
+    if actions.just_pressed(Action::DebugLightOnly) {
+        for (_, material) in overlay_materials.iter_mut() {
+            material.light_only = !material.light_only;
+        }
+    }
+    if actions.just_pressed(Action::DebugLightEditor) {
+        debug_context.should_render_light_editor = !debug_context.should_render_light_editor;
+    }
+    if actions.just_pressed(Action::DebugPixelCamera) {
+        if let Some(mut pixel_camera) = pixel_camera {
+            pixel_camera.enabled = !pixel_camera.enabled;
+        }
+    }
 }
@@ -0,0 +1,195 @@
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{camera::RenderTarget, render_resource::TextureFormat},
+    ui::RelativeCursorPosition,
+};
+
+use crate::{
+    render_layers_union, spawn_render_target_image, CameraBoundary, CameraEvent, MainCamera,
+    Person, SelectedPerson, RENDER_LAYER_MAIN1, RENDER_LAYER_MINIMAP,
+};
+
+const MINIMAP_SIZE: UVec2 = UVec2::new(160, 160);
+/// Size of the person markers, in minimap pixels.
+const MARKER_SIZE: f32 = 4.0;
+
+#[derive(Component)]
+pub struct MinimapCamera;
+
+/// UI image of the minimap, clicking it moves the main camera there.
+#[derive(Component)]
+pub struct Minimap;
+
+/// Area of the main camera view on the minimap.
+#[derive(Component)]
+pub struct MinimapView;
+
+/// Dot following a `Person` on the minimap.
+#[derive(Component)]
+pub struct MinimapMarker(pub Entity);
+
+pub fn setup_minimap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let minimap_texture =
+        spawn_render_target_image(&mut images, MINIMAP_SIZE, TextureFormat::Bgra8UnormSrgb);
+    commands.spawn((
+        Camera2dBundle {
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::Custom(Color::rgb(0.05, 0.06, 0.08)),
+            },
+            camera: Camera {
+                order: -2,
+                target: RenderTarget::Image(minimap_texture.clone()),
+                ..default()
+            },
+            ..default()
+        },
+        UiCameraConfig { show_ui: false },
+        render_layers_union(RENDER_LAYER_MAIN1, RENDER_LAYER_MINIMAP),
+        MinimapCamera,
+    ));
+
+    commands.spawn((
+        ImageBundle {
+            image: UiImage::new(minimap_texture),
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                width: Val::Px(MINIMAP_SIZE.x as f32),
+                height: Val::Px(MINIMAP_SIZE.y as f32),
+                ..default()
+            },
+            ..default()
+        },
+        Interaction::default(),
+        RelativeCursorPosition::default(),
+        Minimap,
+    ));
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, 0.25),
+                custom_size: Some(Vec2::ONE),
+                ..default()
+            },
+            ..default()
+        },
+        RENDER_LAYER_MINIMAP,
+        MinimapView,
+    ));
+}
+
+/// Fits the minimap to the camera boundary and shows the main camera view.
+pub fn update_minimap_view(
+    boundary: Res<CameraBoundary>,
+    mut minimap_camera_query: Query<&mut Transform, With<MinimapCamera>>,
+    main_camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut view_query: Query<&mut Transform, (With<MinimapView>, Without<MinimapCamera>)>,
+) {
+    let size = boundary.positive - boundary.negative;
+    let scale = (size / MINIMAP_SIZE.as_vec2()).max_element();
+    let minimap_transform = Transform {
+        translation: ((boundary.negative + boundary.positive) / 2.0).extend(999.0),
+        scale: Vec3::new(scale, scale, 1.0),
+        ..default()
+    };
+    for mut transform in minimap_camera_query.iter_mut() {
+        if *transform != minimap_transform {
+            *transform = minimap_transform;
+        }
+    }
+
+    let Ok((camera, camera_transform)) = main_camera_query.get_single() else {
+        return;
+    };
+    let Some(view_size) = camera.logical_viewport_size() else {
+        return;
+    };
+    let (camera_scale, _, camera_translation) = camera_transform.to_scale_rotation_translation();
+    for mut transform in view_query.iter_mut() {
+        transform.translation = camera_translation.truncate().extend(900.0);
+        transform.scale = (view_size * camera_scale.truncate()).extend(1.0);
+    }
+}
+
+pub fn update_minimap_markers(
+    mut commands: Commands,
+    selected_person: Option<Res<SelectedPerson>>,
+    minimap_camera_query: Query<&Transform, With<MinimapCamera>>,
+    person_query: Query<&Parent, With<Person>>,
+    target_query: Query<&GlobalTransform>,
+    mut marker_query: Query<(Entity, &MinimapMarker, &mut Transform, &mut Sprite)>,
+) {
+    let Ok(minimap_transform) = minimap_camera_query.get_single() else {
+        return;
+    };
+
+    for parent in person_query.iter() {
+        if !marker_query
+            .iter()
+            .any(|(_, marker, _, _)| marker.0 == parent.get())
+        {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::ONE),
+                        ..default()
+                    },
+                    ..default()
+                },
+                RENDER_LAYER_MINIMAP,
+                MinimapMarker(parent.get()),
+            ));
+        }
+    }
+
+    for (entity, marker, mut transform, mut sprite) in marker_query.iter_mut() {
+        let Ok(target_transform) = target_query.get(marker.0) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        transform.translation = target_transform.translation().truncate().extend(950.0);
+        transform.scale = Vec3::new(
+            MARKER_SIZE * minimap_transform.scale.x,
+            MARKER_SIZE * minimap_transform.scale.y,
+            1.0,
+        );
+        let color = if selected_person
+            .as_ref()
+            .is_some_and(|selected| selected.0 == marker.0)
+        {
+            Color::YELLOW
+        } else {
+            Color::GREEN
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+/// Moves the main camera to the clicked point of the minimap, dragging keeps moving it.
+pub fn control_minimap(
+    minimap_query: Query<(&Interaction, &RelativeCursorPosition), With<Minimap>>,
+    minimap_camera_query: Query<(&Camera, &GlobalTransform), With<MinimapCamera>>,
+    mut camera_events: EventWriter<CameraEvent>,
+) {
+    let Ok((camera, camera_transform)) = minimap_camera_query.get_single() else {
+        return;
+    };
+    for (interaction, cursor) in minimap_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(position) = cursor.normalized.filter(|_| cursor.mouse_over()) else {
+            continue;
+        };
+        if let Some(world_position) =
+            camera.viewport_to_world_2d(camera_transform, position * MINIMAP_SIZE.as_vec2())
+        {
+            camera_events.send(CameraEvent::Jump(world_position));
+        }
+    }
+}
@@ -1,15 +1,25 @@
+mod ambient_light;
+mod animation;
 mod cameras;
 mod day_cycle;
 mod debug;
+mod minimap;
 mod path_finder;
 mod person;
+mod room;
 mod shelter;
 mod util;
+mod weather;
 
+pub use ambient_light::*;
+pub use animation::*;
 pub use cameras::*;
 pub use day_cycle::*;
 pub use debug::*;
+pub use minimap::*;
 pub use path_finder::*;
 pub use person::*;
+pub use room::*;
 pub use shelter::*;
 pub use util::*;
+pub use weather::*;
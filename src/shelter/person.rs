@@ -1,15 +1,10 @@
-use bevy::{
-    prelude::*,
-    render::{
-        render_resource::{AddressMode, FilterMode, SamplerDescriptor},
-        texture::{CompressedImageFormats, ImageSampler, ImageType},
-    },
-    sprite::MaterialMesh2dBundle,
-};
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 
 use crate::{
-    moveable_bundle, selectable_bundle, spatial_bundle_tile, transform_2d, MoveTo, OutlineMaterial,
-    WorldCursor, OUTLINE_MATERIAL_MESH_HANDLE, TILE_SIZE,
+    kinematic_moveable_bundle, moveable_bundle, selectable_bundle, spatial_bundle_tile,
+    transform_2d, Action, CurrentRoom, MoveTo, MovementMode, Outline, OutlineMaterial,
+    OutlineState, PersonAnimation, PickEvent, PickState, PixelSnap, Room, TextureLoader,
+    TextureSettings, WorldCursor, OUTLINE_MATERIAL_MESH_HANDLE, TILE_SIZE,
 };
 
 #[derive(Resource)]
@@ -19,63 +14,120 @@ pub struct Person;
 
 pub fn spawn_person(
     commands: &mut Commands,
-    images: &mut Assets<Image>,
+    asset_server: &AssetServer,
+    texture_loader: &mut TextureLoader,
     outline_materials: &mut Assets<OutlineMaterial>,
     position: IVec2,
+    movement_mode: MovementMode,
 ) -> Entity {
-    let person_image = load_texture("demo/person.png");
-    let person_size = person_image.texture_descriptor.size;
-    let person_size = Vec2::new(person_size.width as f32, person_size.height as f32);
-    commands
-        .spawn((
-            spatial_bundle_tile(position, IVec2::ONE, 100.0),
-            moveable_bundle(80.0),
-        ))
+    let person_image = texture_loader.load(
+        asset_server,
+        "demo/person_sheet.png",
+        TextureSettings::default(),
+    );
+    let mut person = commands.spawn(spatial_bundle_tile(position, IVec2::ONE, 100.0));
+    match movement_mode {
+        MovementMode::Dynamic => person.insert(moveable_bundle(80.0)),
+        MovementMode::Kinematic => person.insert(kinematic_moveable_bundle(80.0)),
+    };
+    person.insert(CurrentRoom::default());
+    person
         .with_children(|parent| {
             parent.spawn((
                 MaterialMesh2dBundle {
                     mesh: OUTLINE_MATERIAL_MESH_HANDLE.typed().into(),
-                    material: outline_materials.add(OutlineMaterial {
-                        color: Color::WHITE,
-                        line_width: 0,
-                        texture: images.add(person_image),
-                    }),
-                    transform: transform_2d(-Vec2::new(0.5, 0.5), person_size / TILE_SIZE, 100.0),
+                    material: outline_materials.add(OutlineMaterial::new(person_image)),
+                    // Sized by `update_person_transform` once the texture is loaded.
+                    transform: transform_2d(-Vec2::new(0.5, 0.5), Vec2::ONE, 100.0),
                     ..default()
                 },
                 selectable_bundle(),
+                PixelSnap::default(),
+                PersonAnimation::default(),
+                Outline::default(),
                 Person,
             ));
         })
         .id()
 }
 
+type PersonSpriteQuery<'a> = (
+    &'a Handle<OutlineMaterial>,
+    Option<&'a PersonAnimation>,
+    &'a mut Transform,
+);
+
+pub fn update_person_transform(
+    images: Res<Assets<Image>>,
+    materials: Res<Assets<OutlineMaterial>>,
+    mut person_query: Query<PersonSpriteQuery, With<Person>>,
+) {
+    for (material_handle, animation, mut transform) in person_query.iter_mut() {
+        let Some(image) = materials
+            .get(material_handle)
+            .and_then(|material| images.get(&material.texture))
+        else {
+            continue;
+        };
+        let frame_size = match animation {
+            Some(animation) => image.size() / animation.grid.max(UVec2::ONE).as_vec2(),
+            None => image.size(),
+        };
+        let person_transform = transform_2d(-Vec2::new(0.5, 0.5), frame_size / TILE_SIZE, 100.0);
+        if *transform != person_transform {
+            *transform = person_transform;
+        }
+    }
+}
+
 pub fn select_person(
     mut commands: Commands,
     world_cursor: Res<WorldCursor>,
-    buttons: Res<Input<MouseButton>>,
+    actions: Res<Input<Action>>,
+    pick_state: Res<PickState>,
+    mut pick_events: EventReader<PickEvent>,
     mut selected_person: ResMut<SelectedPerson>,
-    mut materials: ResMut<Assets<OutlineMaterial>>,
-    person_query: Query<(Entity, &Parent, &Handle<OutlineMaterial>), With<Person>>,
+    person_query: Query<&Parent, With<Person>>,
 ) {
-    let mut person_under_cursor: Option<Entity> = None;
-    for (person, parent, handle) in person_query.iter() {
-        let under_cursor = world_cursor.entities_below.contains(&person);
-        if let Some(material) = materials.get_mut(handle) {
-            material.line_width = if under_cursor { 1 } else { 0 };
-        }
-        if under_cursor {
-            person_under_cursor = Some(parent.get());
+    for event in pick_events.iter() {
+        if let PickEvent::Click(entity) = *event {
+            if let Ok(parent) = person_query.get(entity) {
+                selected_person.0 = parent.get();
+            }
         }
     }
 
-    if buttons.just_pressed(MouseButton::Left) {
-        if let Some(person) = person_under_cursor {
-            selected_person.0 = person;
+    let over_person = pick_state
+        .hovered
+        .is_some_and(|entity| person_query.contains(entity));
+    // Clicks on the UI, e.g. the minimap, are not for the world.
+    if actions.just_pressed(Action::Select) && !pick_state.over_ui && !over_person {
+        commands
+            .entity(selected_person.0)
+            .insert(MoveTo(world_cursor.position));
+    }
+}
+
+/// Outlines the selected person and the hovered person or room, unpowered rooms look
+/// disabled.
+pub fn update_outline_states(
+    pick_state: Res<PickState>,
+    selected_person: Res<SelectedPerson>,
+    mut outline_query: Query<(Entity, &mut Outline, Option<&Parent>, Option<&Room>)>,
+) {
+    for (entity, mut outline, parent, room) in outline_query.iter_mut() {
+        let under_cursor = pick_state.hovered == Some(entity);
+        let state = if parent.is_some_and(|parent| parent.get() == selected_person.0) {
+            OutlineState::Selected
+        } else if !under_cursor {
+            OutlineState::None
+        } else if room.is_some_and(|room| !room.powered) {
+            OutlineState::Disabled
         } else {
-            commands
-                .entity(selected_person.0)
-                .insert(MoveTo(world_cursor.position));
+            OutlineState::Hovered
+        };
+        if outline.state != state {
+            outline.state = state;
         }
     }
 }
@@ -116,30 +168,3 @@ pub fn select_person(
 //         }
 //     }
 // }
-
-// hacky
-fn load_texture(texture_path: &str) -> Image {
-    let real_path = "assets/".to_owned() + texture_path;
-    let ext = std::path::Path::new(&real_path)
-        .extension()
-        .unwrap()
-        .to_str()
-        .unwrap();
-    let img_bytes = std::fs::read(&real_path).unwrap();
-    let mut image = Image::from_buffer(
-        &img_bytes,
-        ImageType::Extension(ext),
-        CompressedImageFormats::all(),
-        true,
-    )
-    .unwrap();
-    image.sampler_descriptor = ImageSampler::Descriptor(SamplerDescriptor {
-        address_mode_u: AddressMode::ClampToBorder,
-        address_mode_v: AddressMode::ClampToBorder,
-        mag_filter: FilterMode::Nearest,
-        min_filter: FilterMode::Nearest,
-        mipmap_filter: FilterMode::Nearest,
-        ..default()
-    });
-    image
-}
@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{trigger_bundle, TriggerBundle, TriggerEvent};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoomKind {
+    #[default]
+    Empty,
+    Living,
+    Workshop,
+    Storage,
+}
+
+impl RoomKind {
+    pub fn ambient_color(&self) -> Color {
+        match self {
+            RoomKind::Empty => Color::rgb(0.6, 0.6, 0.6),
+            RoomKind::Living => Color::rgb(1.0, 0.85, 0.6),
+            RoomKind::Workshop => Color::rgb(0.85, 0.95, 1.0),
+            RoomKind::Storage => Color::rgb(0.8, 0.8, 0.7),
+        }
+    }
+
+    pub fn ambient_intensity(&self) -> f32 {
+        match self {
+            RoomKind::Empty => 0.05,
+            RoomKind::Living => 0.3,
+            RoomKind::Workshop => 0.4,
+            RoomKind::Storage => 0.15,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Room {
+    pub position: IVec2,
+    pub kind: RoomKind,
+    pub powered: bool,
+}
+
+/// Entities inside the room, counted by its `RoomTrigger`.
+#[derive(Component, Debug, Default)]
+pub struct RoomOccupants(pub HashSet<Entity>);
+
+impl RoomOccupants {
+    pub fn count(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Trigger volume of the parent `Room`, covering it.
+#[derive(Component, Debug, Default)]
+pub struct RoomTrigger;
+
+pub fn room_trigger_bundle() -> (SpatialBundle, TriggerBundle, RoomTrigger) {
+    (SpatialBundle::default(), trigger_bundle(), RoomTrigger)
+}
+
+/// Room the moveable is in, the last entered when it overlaps several.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CurrentRoom(pub Option<Entity>);
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomEvent {
+    Enter { room: Entity, entity: Entity },
+    Leave { room: Entity, entity: Entity },
+}
+
+pub fn update_room_occupancy(
+    mut trigger_events: EventReader<TriggerEvent>,
+    mut room_events: EventWriter<RoomEvent>,
+    trigger_query: Query<&Parent, With<RoomTrigger>>,
+    mut occupants_query: Query<(Entity, &mut RoomOccupants)>,
+    mut current_room_query: Query<&mut CurrentRoom>,
+) {
+    for event in trigger_events.iter() {
+        let (TriggerEvent::Enter { trigger, entity } | TriggerEvent::Leave { trigger, entity }) =
+            *event;
+        let Ok(room) = trigger_query.get(trigger).map(|parent| parent.get()) else {
+            continue;
+        };
+        let Ok((_, mut occupants)) = occupants_query.get_mut(room) else {
+            continue;
+        };
+        match event {
+            TriggerEvent::Enter { .. } => {
+                if !occupants.0.insert(entity) {
+                    continue;
+                }
+                if let Ok(mut current_room) = current_room_query.get_mut(entity) {
+                    current_room.0 = Some(room);
+                }
+                room_events.send(RoomEvent::Enter { room, entity });
+            }
+            TriggerEvent::Leave { .. } => {
+                if !occupants.0.remove(&entity) {
+                    continue;
+                }
+                if let Ok(mut current_room) = current_room_query.get_mut(entity) {
+                    if current_room.0 == Some(room) {
+                        // Falls back to another room it still overlaps.
+                        current_room.0 = occupants_query
+                            .iter()
+                            .find(|(_, occupants)| occupants.0.contains(&entity))
+                            .map(|(other_room, _)| other_room);
+                    }
+                }
+                room_events.send(RoomEvent::Leave { room, entity });
+            }
+        }
+    }
+}
@@ -1,11 +1,13 @@
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 
 use crate::{
-    freeform_polygon_mesh, pure_color_bundle_tile, solid_bundle, spawn_person, sprite_bundle_tile,
-    stair_bundle, transform_2d_tile_m, transform_bundle_tile, world_coor, Background,
-    BackgroundBundle, BackgroundMaterial, BackgroundMaterialImages, BackgroundRepeat,
-    CameraBoundary, CameraMode, GameDateTimeText, Light2dFreeformMaterial, LightIntensity,
-    OutlineMaterial, PathFinder, SelectedPerson, RENDER_LAYER_LIGHT1, RENDER_LAYER_MAIN2,
+    freeform_polygon_mesh, pure_color_bundle_tile, room_trigger_bundle, selectable_bundle,
+    solid_bundle, spawn_person, spawn_weather_fog, stair_bundle, transform_2d_tile,
+    transform_2d_tile_m, transform_bundle_tile, world_coor, AmbientLightVolume, BackgroundStack,
+    BackgroundTarget, CameraBoundary, CameraMode, ForegroundCamera, GameDateTimeText,
+    Light2dFreeformMaterial, MovementMode, Outline, OutlineMaterial, PathFinder, Room, RoomKind,
+    RoomOccupants, SelectedPerson, TextureLoader, WeatherArea, OUTLINE_MATERIAL_MESH_HANDLE,
+    RENDER_LAYER_FOREGROUND, RENDER_LAYER_LIGHT1, RENDER_LAYER_MAIN2, RENDER_LAYER_MAIN_ONLY,
 };
 
 fn spawn_stair_pair(commands: &mut Commands, position1: IVec2, position2: IVec2) {
@@ -21,11 +23,33 @@ fn spawn_stair_pair(commands: &mut Commands, position1: IVec2, position2: IVec2)
     ));
 }
 
-fn spawn_solid(commands: &mut Commands, position: IVec2, size: IVec2) {
+/// Returns the area covered by the solid, in world units.
+fn spawn_solid(commands: &mut Commands, position: IVec2, size: IVec2) -> Rect {
     commands.spawn((
         pure_color_bundle_tile(position, size, 10.0, Color::BLACK),
         solid_bundle(),
     ));
+    Rect::from_corners(world_coor(position), world_coor(position + size))
+}
+
+fn spawn_ambient_light(
+    commands: &mut Commands,
+    mesh: &Handle<Mesh>,
+    materials: &mut Assets<Light2dFreeformMaterial>,
+    position: IVec2,
+    size: IVec2,
+    volume: AmbientLightVolume,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: mesh.clone().into(),
+            material: materials.add(Light2dFreeformMaterial::default()),
+            transform: transform_2d_tile_m(position, size, 1.0),
+            ..default()
+        },
+        RENDER_LAYER_LIGHT1,
+        volume,
+    ));
 }
 
 const BORDER: i32 = 6;
@@ -36,6 +60,13 @@ const ROOM_WIDTH: i32 = 12;
 const OUTSIDE_HEIGHT: i32 = 25;
 const LAYER_HEIGHT: i32 = 11;
 
+const ROOM_KINDS: [RoomKind; 4] = [
+    RoomKind::Living,
+    RoomKind::Workshop,
+    RoomKind::Storage,
+    RoomKind::Empty,
+];
+
 pub fn shelter_position(room: IVec2) -> IVec2 {
     // temp
     let width = (ROOM_WIDTH * 7 + STAIR_WIDTH) / 2;
@@ -48,23 +79,27 @@ pub fn shelter_position(room: IVec2) -> IVec2 {
 pub fn setup_shelter(
     mut commands: Commands,
     asset: ResMut<AssetServer>,
+    mut texture_loader: ResMut<TextureLoader>,
     mut meshes: ResMut<Assets<Mesh>>,
-    ref mut images: ResMut<Assets<Image>>,
-    mut background_materials: ResMut<Assets<BackgroundMaterial>>,
     mut light2d_freeform_materials: ResMut<Assets<Light2dFreeformMaterial>>,
     mut outline_materials: ResMut<Assets<OutlineMaterial>>,
+    foreground_camera_query: Query<Entity, With<ForegroundCamera>>,
 ) {
     let id = spawn_person(
         &mut commands,
-        images,
+        &asset,
+        &mut texture_loader,
         &mut outline_materials,
         shelter_position(IVec2::new(3, 1)),
+        MovementMode::Dynamic,
     );
     spawn_person(
         &mut commands,
-        images,
+        &asset,
+        &mut texture_loader,
         &mut outline_materials,
         shelter_position(IVec2::new(2, 1)),
+        MovementMode::Kinematic,
     );
     commands.insert_resource(SelectedPerson(id));
 
@@ -88,35 +123,20 @@ pub fn setup_shelter(
     ));
 
     // Background
-    let mut spawn_background = |texture_path: &str, speed: Vec2, z: f32| {
-        let background_images =
-            BackgroundMaterialImages::simple(images, BackgroundRepeat::None, texture_path);
-        commands.spawn((
-            BackgroundBundle {
-                material_bundle: BackgroundMaterial::bundle(
-                    &mut background_materials,
-                    background_images,
-                ),
-                background: Background {
-                    position: Vec2::new(0.0, -384.0),
-                    offset: Vec2::new(0.0, 1.0),
-                    speed,
-                    z,
-                    scale: 1.0,
-                    ..default()
-                },
-            },
-            // RENDER_LAYER_MAIN1,
-        ));
-    };
-
-    spawn_background("b1.png", Vec2::new(0.0, 0.5), 0.1);
-    // spawn_background("demo/1.png", Vec2::new(0.0, 0.5), 0.1);
-    // spawn_background("demo/2.png", Vec2::new(0.0, 0.2), 0.2);
-    // spawn_background("demo/3.png", Vec2::new(0.0, 0.1), 0.3);
-    // spawn_background("demo/4.png", Vec2::new(0.0, 0.0), 0.4);
-    // spawn_background("demo/5.png", Vec2::new(0.0, 0.0), 0.5);
-    // spawn_background("demo/6.png", Vec2::new(0.0, 0.0), 0.6);
+    commands.spawn((
+        SpatialBundle::default(),
+        asset.load::<BackgroundStack, _>("demo/shelter.background.ron"),
+        RENDER_LAYER_MAIN_ONLY,
+    ));
+    // Foreground, drawn after the light merge so it isn't lit. Lit foreground layers go in
+    // the background stack with a z above the shelter. Sized to the window the foreground
+    // camera draws to, rather than to the main camera's image.
+    commands.spawn((
+        SpatialBundle::default(),
+        asset.load::<BackgroundStack, _>("demo/shelter_foreground.background.ron"),
+        BackgroundTarget(foreground_camera_query.single()),
+        RENDER_LAYER_FOREGROUND,
+    ));
 
     // Spawn shelter
     let room_number = IVec2::new(7, 5);
@@ -124,15 +144,21 @@ pub fn setup_shelter(
     let width = (ROOM_WIDTH * room_number.x + STAIR_WIDTH) / 2;
     let height = room_number.y * (LAYER_HEIGHT + INTERVAL) + BORDER;
 
+    // From the outer edges of the walls and the bottom floor to the top of the outdoor area.
+    let boundary = Rect::from_corners(
+        world_coor(IVec2::new(-width - BORDER, -height)),
+        world_coor(IVec2::new(width + BORDER, OUTSIDE_HEIGHT)),
+    );
     commands.insert_resource(CameraBoundary {
         max_width: 960.0,
-        negative: Vec2::new(-960.0 / 2.0, -height as f32 + 0.5 * BORDER as f32) * 2.0,
-        positive: Vec2::new(960.0 / 2.0, OUTSIDE_HEIGHT as f32) * 2.0,
+        negative: boundary.min,
+        positive: boundary.max,
         scale_level: 1,
         mode: CameraMode::Free,
+        trauma: 0.0,
     });
 
-    // Background light
+    // Ambient light
     let mesh = meshes.add(freeform_polygon_mesh(
         vec![
             Vec2::new(0.0, 1.0),
@@ -142,66 +168,75 @@ pub fn setup_shelter(
         ],
         0.0,
     ));
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: mesh.clone().into(),
-            material: light2d_freeform_materials.add(Light2dFreeformMaterial { ..default() }),
-            transform: transform_2d_tile_m(
-                IVec2::new(-width - 5, 0),
-                IVec2::new(width * 2 + 10, 50),
-                1.0,
-            ),
-            ..default()
-        },
-        RENDER_LAYER_LIGHT1,
-        LightIntensity {
-            max: 1.0,
-            min: 0.1,
-            addition: 0.0,
-        },
-    ));
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: mesh.clone().into(),
-            material: light2d_freeform_materials.add(Light2dFreeformMaterial {
-                color: Color::rgb(0.4, 0.4, 0.4),
-                ..default()
-            }),
-            transform: transform_2d_tile_m(
-                IVec2::new(-width - 5, -height - 50),
-                IVec2::new(width * 2 + 10, height + 50),
-                1.0,
-            ),
-            ..default()
-        },
-        RENDER_LAYER_LIGHT1,
+    spawn_ambient_light(
+        &mut commands,
+        &mesh,
+        &mut light2d_freeform_materials,
+        IVec2::new(-width - 5, 0),
+        IVec2::new(width * 2 + 10, 50),
+        AmbientLightVolume::Outdoor,
+    );
+
+    // Weather
+    let weather_area = Rect::from_corners(
+        world_coor(IVec2::new(-width - 5, 0)),
+        world_coor(IVec2::new(width + 5, 50)),
+    );
+    spawn_weather_fog(&mut commands, weather_area);
+    commands.insert_resource(WeatherArea(weather_area));
+    spawn_ambient_light(
+        &mut commands,
+        &mesh,
+        &mut light2d_freeform_materials,
+        IVec2::new(-width - 5, -height - 50),
+        IVec2::new(width * 2 + 10, height + 50),
+        AmbientLightVolume::Indoor,
+    );
+
+    // Opening at the top of the stair shaft
+    let opening_mesh = meshes.add(freeform_polygon_mesh(
+        vec![
+            Vec2::new(0.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 0.0),
+        ],
+        0.2,
     ));
+    spawn_ambient_light(
+        &mut commands,
+        &opening_mesh,
+        &mut light2d_freeform_materials,
+        IVec2::new(width - STAIR_WIDTH, -(LAYER_HEIGHT + INTERVAL)),
+        IVec2::new(STAIR_WIDTH, LAYER_HEIGHT + INTERVAL),
+        AmbientLightVolume::Opening { bleed: 0.5 },
+    );
 
     // Move
     let mut platforms = vec![];
     let mut stairs = vec![];
 
     // Solid
-    spawn_solid(
+    let mut solids = spawn_solid(
         &mut commands,
         IVec2::new(-width - BORDER, -height),
         IVec2::new(BORDER, height),
     );
-    spawn_solid(
+    solids = solids.union(spawn_solid(
         &mut commands,
         IVec2::new(width, -height),
         IVec2::new(BORDER, height),
-    );
+    ));
     for i in 0..room_number.y + 1 {
         let floor_y = -i * (LAYER_HEIGHT + INTERVAL);
         let size_y = if i == room_number.y { BORDER } else { INTERVAL };
 
         let y = floor_y - size_y;
-        spawn_solid(
+        solids = solids.union(spawn_solid(
             &mut commands,
             IVec2::new(-width, y),
             IVec2::new(2 * width, size_y),
-        );
+        ));
         platforms.push((
             IVec2::new(-width - 100, y),
             IVec2::new(2 * width + 200, LAYER_HEIGHT + size_y),
@@ -209,17 +244,26 @@ pub fn setup_shelter(
 
         if i != 0 {
             let y = floor_y + (LAYER_HEIGHT + INTERVAL) / 2 - INTERVAL;
-            spawn_solid(
+            solids = solids.union(spawn_solid(
                 &mut commands,
                 IVec2::new(width - 1, y),
                 IVec2::new(1, INTERVAL),
-            );
+            ));
             platforms.push((
                 IVec2::new(width - 2, y),
                 IVec2::new(100, (LAYER_HEIGHT + INTERVAL) / 2),
             ));
         }
     }
+    // The camera and the minimap show the boundary, which shouldn't go past the walls.
+    debug_assert!(
+        boundary.contains(solids.min)
+            && boundary.contains(solids.max)
+            && boundary.width() == solids.width(),
+        "Camera boundary {:?} doesn't fit the shelter {:?}",
+        boundary,
+        solids
+    );
 
     // Stair
     for i in 1..room_number.y + 1 {
@@ -246,12 +290,38 @@ pub fn setup_shelter(
     for y in 0..room_number.y {
         let position_y = -(y + 1) * (LAYER_HEIGHT + INTERVAL);
         for x in 0..room_number.x {
-            commands.spawn(sprite_bundle_tile(
-                IVec2::new(-width + x * ROOM_WIDTH, position_y),
-                IVec2::new(ROOM_WIDTH, LAYER_HEIGHT),
-                9.0,
-                room_wall_image.clone(),
-            ));
+            let position = IVec2::new(-width + x * ROOM_WIDTH, position_y);
+            let size = IVec2::new(ROOM_WIDTH, LAYER_HEIGHT);
+            let room = commands
+                .spawn((
+                    MaterialMesh2dBundle {
+                        mesh: OUTLINE_MATERIAL_MESH_HANDLE.typed().into(),
+                        material: outline_materials
+                            .add(OutlineMaterial::new(room_wall_image.clone())),
+                        transform: transform_2d_tile(position, size, 9.0),
+                        ..default()
+                    },
+                    selectable_bundle(),
+                    Outline::default(),
+                    Room {
+                        position: IVec2::new(x, y),
+                        kind: ROOM_KINDS[y as usize % ROOM_KINDS.len()],
+                        powered: y != room_number.y - 1,
+                    },
+                    RoomOccupants::default(),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(room_trigger_bundle());
+                })
+                .id();
+            spawn_ambient_light(
+                &mut commands,
+                &mesh,
+                &mut light2d_freeform_materials,
+                position,
+                size,
+                AmbientLightVolume::Room(room),
+            );
         }
     }
 }
@@ -0,0 +1,385 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    day_cycle, pure_color_bundle, Action, BackgroundPalette, CameraEvent, GameDateTime,
+    PaletteCurve, PaletteKeyframe, PaletteTimeline,
+};
+
+#[derive(SystemSet, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct WeatherSystems;
+
+/// Updates the `Weather` after the day cycle, together with its particles, fog and lightning.
+/// The overcast goes to the `BackgroundPalette` of the `BackgroundPlugin`.
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WeatherSchedule>()
+            .init_resource::<Weather>()
+            .init_resource::<WeatherArea>()
+            .init_resource::<WeatherRng>()
+            .add_event::<CameraEvent>()
+            .add_systems(
+                Update,
+                (update_weather, update_weather_particles)
+                    .chain()
+                    .after(day_cycle)
+                    .in_set(WeatherSystems),
+            );
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherKind {
+    #[default]
+    Clear,
+    Rain,
+    Snow,
+    Fog,
+    Storm,
+}
+
+impl WeatherKind {
+    pub fn params(self) -> WeatherParams {
+        match self {
+            WeatherKind::Clear => WeatherParams::default(),
+            WeatherKind::Rain => WeatherParams {
+                rain: 0.6,
+                overcast: 0.4,
+                ..default()
+            },
+            WeatherKind::Snow => WeatherParams {
+                snow: 0.7,
+                overcast: 0.3,
+                ..default()
+            },
+            WeatherKind::Fog => WeatherParams {
+                fog: 0.7,
+                overcast: 0.2,
+                ..default()
+            },
+            WeatherKind::Storm => WeatherParams {
+                rain: 1.0,
+                fog: 0.1,
+                overcast: 0.8,
+                lightning: 0.15,
+                ..default()
+            },
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            WeatherKind::Clear => WeatherKind::Rain,
+            WeatherKind::Rain => WeatherKind::Snow,
+            WeatherKind::Snow => WeatherKind::Fog,
+            WeatherKind::Fog => WeatherKind::Storm,
+            WeatherKind::Storm => WeatherKind::Clear,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct WeatherParams {
+    /// Rain drops spawned, `1.0` is a downpour.
+    pub rain: f32,
+    pub snow: f32,
+    /// Opacity of the fog over the outdoor area.
+    pub fog: f32,
+    /// Darkens the outdoor light and fades in the overcast palette.
+    pub overcast: f32,
+    /// Lightning strikes per second.
+    pub lightning: f32,
+}
+
+impl WeatherParams {
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        Self {
+            rain: lerp(self.rain, other.rain),
+            snow: lerp(self.snow, other.snow),
+            fog: lerp(self.fog, other.fog),
+            overcast: lerp(self.overcast, other.overcast),
+            lightning: lerp(self.lightning, other.lightning),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WeatherChange {
+    /// Day in the schedule period.
+    pub day: i32,
+    /// Time of day in `[0, 1)`.
+    pub time: f32,
+    pub kind: WeatherKind,
+}
+
+impl WeatherChange {
+    pub fn new(day: i32, time: f32, kind: WeatherKind) -> Self {
+        Self { day, time, kind }
+    }
+}
+
+/// Weather changes repeating every `period_days`.
+#[derive(Resource)]
+pub struct WeatherSchedule {
+    pub period_days: i32,
+    pub changes: Vec<WeatherChange>,
+    /// Duration of the cross-fade between two weathers, in days.
+    pub transition: f32,
+    /// Palettes faded in by overcast weathers.
+    pub overcast_palette: PaletteTimeline,
+}
+
+impl Default for WeatherSchedule {
+    fn default() -> Self {
+        Self {
+            period_days: 3,
+            changes: vec![
+                WeatherChange::new(0, 0.35, WeatherKind::Rain),
+                WeatherChange::new(0, 0.55, WeatherKind::Clear),
+                WeatherChange::new(1, 0.2, WeatherKind::Fog),
+                WeatherChange::new(1, 0.4, WeatherKind::Clear),
+                WeatherChange::new(1, 0.7, WeatherKind::Storm),
+                WeatherChange::new(2, 0.1, WeatherKind::Clear),
+                WeatherChange::new(2, 0.5, WeatherKind::Snow),
+                WeatherChange::new(2, 0.9, WeatherKind::Clear),
+            ],
+            transition: 0.03,
+            // Night row of `demo/lut.png`.
+            overcast_palette: PaletteTimeline::new(vec![PaletteKeyframe::new(
+                0.0,
+                1,
+                PaletteCurve::Step,
+            )]),
+        }
+    }
+}
+
+impl WeatherSchedule {
+    /// Returns the previous weather, the current one and the progress of the transition.
+    pub fn sample(&self, days: i32, time: f32) -> (WeatherKind, WeatherKind, f32) {
+        let period = self.period_days.max(1) as f32;
+        let now = (days as f32 + time).rem_euclid(period);
+        let mut changes: Vec<_> = self
+            .changes
+            .iter()
+            .map(|change| (change.day as f32 + change.time, change.kind))
+            .collect();
+        changes.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let Some(current) = changes
+            .iter()
+            .rposition(|(start, _)| *start <= now)
+            .or_else(|| {
+                // Before the first change, the last one of the period is still going on.
+                changes.len().checked_sub(1)
+            })
+        else {
+            return (WeatherKind::Clear, WeatherKind::Clear, 1.0);
+        };
+        let previous = (current + changes.len() - 1) % changes.len();
+
+        let (start, kind) = changes[current];
+        let elapsed = (now - start).rem_euclid(period);
+        let progress = if self.transition > 0.0 {
+            (elapsed / self.transition).min(1.0)
+        } else {
+            1.0
+        };
+        (changes[previous].1, kind, progress)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Weather {
+    pub previous: WeatherKind,
+    pub kind: WeatherKind,
+    /// Progress of the transition from `previous` to `kind`.
+    pub transition: f32,
+    /// Replaces the schedule, for debugging.
+    pub forced: Option<WeatherKind>,
+    /// Brightness of the current lightning flash, decays to zero.
+    pub flash: f32,
+}
+
+impl Weather {
+    pub fn params(&self) -> WeatherParams {
+        self.previous
+            .params()
+            .lerp(self.kind.params(), self.transition)
+    }
+
+    /// Multiplier of the outdoor ambient light.
+    pub fn outdoor_light(&self) -> f32 {
+        1.0 - self.params().overcast * 0.6 + self.flash * 2.0
+    }
+}
+
+/// Outdoor region covered by weather effects.
+#[derive(Resource, Default)]
+pub struct WeatherArea(pub Rect);
+
+#[derive(Component)]
+pub struct WeatherParticle {
+    velocity: Vec2,
+    sway: f32,
+}
+
+#[derive(Component)]
+pub struct WeatherFog;
+
+/// Xorshift generator, weather doesn't need more.
+#[derive(Resource)]
+pub struct WeatherRng(pub u32);
+
+impl Default for WeatherRng {
+    fn default() -> Self {
+        Self(0x5eed)
+    }
+}
+
+impl WeatherRng {
+    /// Returns a number in `[0, 1)`.
+    pub fn f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+const RAIN_RATE: f32 = 600.0;
+const SNOW_RATE: f32 = 150.0;
+const FLASH_DECAY: f32 = 4.0;
+const WEATHER_Z: f32 = 20.0;
+
+pub fn update_weather(
+    time: Res<Time>,
+    game_date_time: Res<GameDateTime>,
+    schedule: Res<WeatherSchedule>,
+    mut weather: ResMut<Weather>,
+    mut rng: ResMut<WeatherRng>,
+    mut palette: ResMut<BackgroundPalette>,
+    mut camera_events: EventWriter<CameraEvent>,
+) {
+    let (previous, kind, transition) = match weather.forced {
+        Some(kind) => (kind, kind, 1.0),
+        None => schedule.sample(game_date_time.days, game_date_time.time),
+    };
+    weather.previous = previous;
+    weather.kind = kind;
+    weather.transition = transition;
+
+    let params = weather.params();
+    weather.flash = (weather.flash - FLASH_DECAY * time.delta_seconds()).max(0.0);
+    if !game_date_time.paused && rng.f32() < params.lightning * time.delta_seconds() {
+        weather.flash = 0.6 + rng.f32() * 0.4;
+        camera_events.send(CameraEvent::Shake(weather.flash * 0.5));
+    }
+
+    if palette.overlay.keyframes() != schedule.overcast_palette.keyframes() {
+        palette.overlay = schedule.overcast_palette.clone();
+    }
+    if palette.overlay_weight != params.overcast {
+        palette.overlay_weight = params.overcast;
+    }
+}
+
+/// Weather resources the particles are spawned from.
+#[derive(SystemParam)]
+pub struct WeatherParticleSource<'w> {
+    weather: Res<'w, Weather>,
+    area: Res<'w, WeatherArea>,
+    rng: ResMut<'w, WeatherRng>,
+}
+
+pub fn update_weather_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_date_time: Res<GameDateTime>,
+    source: WeatherParticleSource,
+    mut particle_query: Query<(Entity, &WeatherParticle, &mut Transform)>,
+    mut fog_query: Query<&mut Sprite, With<WeatherFog>>,
+) {
+    let WeatherParticleSource {
+        weather,
+        area,
+        mut rng,
+    } = source;
+    let params = weather.params();
+    let area = area.0;
+    if area.is_empty() {
+        return;
+    }
+
+    let delta = time.delta_seconds();
+    for (entity, particle, mut transform) in particle_query.iter_mut() {
+        let sway = (time.elapsed_seconds_wrapped() * 2.0 + particle.sway).sin() * particle.sway;
+        transform.translation += (particle.velocity + Vec2::new(sway, 0.0)).extend(0.0) * delta;
+        if transform.translation.y < area.min.y {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    if !game_date_time.paused {
+        let mut spawn = |rate: f32, size: Vec2, color: Color, velocity: Vec2, sway: f32| {
+            let count = rate * delta;
+            let count = count.floor() as usize + usize::from(rng.f32() < count.fract());
+            for _ in 0..count {
+                let position = Vec2::new(area.min.x + rng.f32() * area.width(), area.max.y);
+                let speed = 0.8 + rng.f32() * 0.4;
+                commands.spawn((
+                    pure_color_bundle(position, size, WEATHER_Z, color),
+                    WeatherParticle {
+                        velocity: velocity * speed,
+                        sway: sway * rng.f32(),
+                    },
+                ));
+            }
+        };
+        spawn(
+            params.rain * RAIN_RATE,
+            Vec2::new(1.0, 6.0),
+            Color::rgba(0.7, 0.8, 1.0, 0.6),
+            Vec2::new(-40.0, -420.0),
+            0.0,
+        );
+        spawn(
+            params.snow * SNOW_RATE,
+            Vec2::new(2.0, 2.0),
+            Color::rgba(1.0, 1.0, 1.0, 0.9),
+            Vec2::new(-10.0, -40.0),
+            15.0,
+        );
+    }
+
+    for mut sprite in fog_query.iter_mut() {
+        if sprite.color.a() != params.fog {
+            sprite.color.set_a(params.fog);
+        }
+    }
+}
+
+pub fn spawn_weather_fog(commands: &mut Commands, area: Rect) {
+    commands.spawn((
+        pure_color_bundle(
+            area.min,
+            area.size(),
+            WEATHER_Z + 1.0,
+            Color::rgba(0.75, 0.78, 0.8, 0.0),
+        ),
+        WeatherFog,
+    ));
+}
+
+pub fn debug_control_weather(mut weather: ResMut<Weather>, actions: Res<Input<Action>>) {
+    if actions.just_pressed(Action::CycleWeather) {
+        weather.forced = match weather.forced {
+            None => Some(WeatherKind::Clear),
+            Some(WeatherKind::Storm) => None,
+            Some(kind) => Some(kind.next()),
+        };
+        info!("Weather forced to {:?}", weather.forced);
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::{
+    asset::HandleId,
+    prelude::*,
+    render::{
+        render_resource::{AddressMode, FilterMode, SamplerDescriptor, TextureFormat},
+        texture::ImageSampler,
+    },
+    utils::HashMap,
+};
+
+pub struct TexturePlugin;
+
+impl Plugin for TexturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TextureLoader>()
+            .add_systems(PreUpdate, apply_texture_settings);
+    }
+}
+
+/// Overrides applied to an image once the asset server has loaded it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureSettings {
+    /// Reinterprets the image data, the new format must have the same texel size.
+    pub format: Option<TextureFormat>,
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+}
+
+impl Default for TextureSettings {
+    fn default() -> Self {
+        Self {
+            format: None,
+            address_mode_u: AddressMode::ClampToBorder,
+            address_mode_v: AddressMode::ClampToBorder,
+        }
+    }
+}
+
+impl TextureSettings {
+    fn sampler_descriptor(&self) -> SamplerDescriptor<'static> {
+        SamplerDescriptor {
+            address_mode_u: self.address_mode_u,
+            address_mode_v: self.address_mode_v,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..default()
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct TextureLoader {
+    settings: HashMap<HandleId, TextureSettings>,
+    /// Images whose settings changed, applied even if no asset event comes because they are
+    /// already loaded.
+    pending: Vec<HandleId>,
+}
+
+impl TextureLoader {
+    pub fn load(
+        &mut self,
+        asset_server: &AssetServer,
+        path: &str,
+        settings: TextureSettings,
+    ) -> Handle<Image> {
+        let handle: Handle<Image> = asset_server.load(path);
+        match self.settings.get(&handle.id()) {
+            Some(previous) if *previous == settings => {}
+            previous => {
+                if let Some(previous) = previous {
+                    warn!(
+                        "Texture {} loaded again with different settings, {:?} replaces {:?}.",
+                        path, settings, previous
+                    );
+                }
+                self.settings.insert(handle.id(), settings);
+                self.pending.push(handle.id());
+            }
+        }
+        handle
+    }
+}
+
+fn apply_texture_settings(
+    mut texture_loader: ResMut<TextureLoader>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let mut handles: Vec<Handle<Image>> =
+        texture_loader.pending.drain(..).map(Handle::weak).collect();
+    for event in image_events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                handles.push(handle.clone_weak())
+            }
+            AssetEvent::Removed { handle } => {
+                texture_loader.settings.remove(&handle.id());
+            }
+        }
+    }
+
+    for handle in handles.iter() {
+        let Some(settings) = texture_loader.settings.get(&handle.id()) else {
+            continue;
+        };
+        let Some(image) = images.get(handle) else {
+            continue;
+        };
+
+        let format = settings.format.unwrap_or(image.texture_descriptor.format);
+        let sampler_descriptor = settings.sampler_descriptor();
+        let sampler_applied = match &image.sampler_descriptor {
+            ImageSampler::Descriptor(descriptor) => *descriptor == sampler_descriptor,
+            ImageSampler::Default => false,
+        };
+        if format == image.texture_descriptor.format && sampler_applied {
+            continue;
+        }
+        if format.block_size(None) != image.texture_descriptor.format.block_size(None) {
+            error!(
+                "Can not reinterpret texture {:?} from {:?} to {:?}.",
+                handle, image.texture_descriptor.format, format
+            );
+            texture_loader.settings.remove(&handle.id());
+            continue;
+        }
+
+        // Emits another modified event, which is skipped above.
+        let image = images.get_mut(handle).unwrap();
+        image.texture_descriptor.format = format;
+        image.sampler_descriptor = ImageSampler::Descriptor(sampler_descriptor);
+    }
+}